@@ -0,0 +1,257 @@
+use bevy::{
+	input::{touch::Touch, InputSystem},
+	prelude::*,
+	render::camera::Camera2d,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	game::shop::{cursor_world_position, screen_to_world},
+	settings::Settings,
+	view::View,
+};
+
+/// Turns keys and gamepad buttons into `ActionPressed` events using the bindings in `Settings`,
+/// and the mouse or a finger into the `Pointer` the shop is played with. Other input methods can
+/// send the same events or move the same pointer.
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_event::<ActionPressed>()
+			.init_resource::<Pointer>()
+			.add_system_to_stage(CoreStage::PreUpdate, bound_actions.after(InputSystem))
+			.add_system_to_stage(CoreStage::PreUpdate, mouse_pointer.after(InputSystem))
+			.add_system_to_stage(
+				CoreStage::PreUpdate,
+				touch_pointer.after(InputSystem).after(mouse_pointer),
+			);
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameAction {
+	Up,
+	Down,
+	Left,
+	Right,
+	/// Presses the focused button, or picks up / drops what's under the shop cursor
+	Select,
+	/// Puts back whatever the shop cursor is holding
+	Cancel,
+	Sell,
+	Freeze,
+	Reroll,
+	Battle,
+}
+
+pub struct ActionPressed(pub GameAction);
+
+/// Which keys and gamepad buttons trigger which actions, several can share an action. Saved with
+/// the rest of `Settings`, so remapping is a matter of editing `settings.json`.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ActionMap {
+	pub keys: Vec<(KeyCode, GameAction)>,
+	pub buttons: Vec<(GamepadButtonType, GameAction)>,
+}
+
+impl Default for ActionMap {
+	fn default() -> Self {
+		Self {
+			keys: vec![
+				(KeyCode::Up, GameAction::Up),
+				(KeyCode::W, GameAction::Up),
+				(KeyCode::Down, GameAction::Down),
+				(KeyCode::S, GameAction::Down),
+				(KeyCode::Left, GameAction::Left),
+				(KeyCode::A, GameAction::Left),
+				(KeyCode::Right, GameAction::Right),
+				(KeyCode::D, GameAction::Right),
+				(KeyCode::Return, GameAction::Select),
+				(KeyCode::Space, GameAction::Select),
+				(KeyCode::Back, GameAction::Cancel),
+				(KeyCode::X, GameAction::Sell),
+				(KeyCode::Delete, GameAction::Sell),
+				(KeyCode::F, GameAction::Freeze),
+				(KeyCode::R, GameAction::Reroll),
+				(KeyCode::B, GameAction::Battle),
+			],
+			buttons: vec![
+				(GamepadButtonType::DPadUp, GameAction::Up),
+				(GamepadButtonType::DPadDown, GameAction::Down),
+				(GamepadButtonType::DPadLeft, GameAction::Left),
+				(GamepadButtonType::DPadRight, GameAction::Right),
+				(GamepadButtonType::South, GameAction::Select),
+				(GamepadButtonType::East, GameAction::Cancel),
+				(GamepadButtonType::West, GameAction::Sell),
+				(GamepadButtonType::North, GameAction::Freeze),
+				(GamepadButtonType::LeftTrigger, GameAction::Reroll),
+				(GamepadButtonType::RightTrigger, GameAction::Battle),
+			],
+		}
+	}
+}
+
+// how long a finger has to rest on something to inspect it, in seconds
+const LONG_PRESS: f32 = 0.5;
+// in logical pixels, so fingers reach as far on any screen density or zoom
+const TOUCH_REACH: f32 = 24.0;
+
+/// Where the shop is being pointed at in world space, and whether it's held down. The mouse or a
+/// finger moves it, the shop cursor moves it when playing with keys or a gamepad.
+#[derive(Default)]
+pub struct Pointer {
+	pub position: Option<Vec2>,
+	pub pressed: bool,
+	pub just_pressed: bool,
+	pub just_released: bool,
+	/// Set the frame a press has been held still long enough to inspect rather than drag
+	pub just_long_pressed: bool,
+	/// Still held after a long press
+	pub inspecting: bool,
+	/// The mouse is resting at `position`. A finger's position is only where it last lifted.
+	pub hovering: bool,
+	/// How far past an object's edges still counts as on it, in world units. Fingers are less
+	/// precise than the mouse.
+	pub reach: f32,
+}
+
+impl Pointer {
+	pub fn press(&mut self, position: Vec2) {
+		self.position = Some(position);
+		self.pressed = true;
+		self.just_pressed = true;
+	}
+
+	pub fn release(&mut self, position: Vec2) {
+		self.position = Some(position);
+		self.pressed = false;
+		self.just_released = true;
+	}
+}
+
+fn bound_actions(
+	settings: Res<Settings>,
+	keys: Res<Input<KeyCode>>,
+	gamepads: Res<Gamepads>,
+	gamepad_buttons: Res<Input<GamepadButton>>,
+	mut actions: EventWriter<ActionPressed>,
+) {
+	let controls = &settings.controls;
+	for (key, action) in controls.keys.iter() {
+		if keys.just_pressed(*key) {
+			actions.send(ActionPressed(*action));
+		}
+	}
+	for (button, action) in controls.buttons.iter() {
+		if gamepads
+			.iter()
+			.any(|gamepad| gamepad_buttons.just_pressed(GamepadButton(*gamepad, *button)))
+		{
+			actions.send(ActionPressed(*action));
+		}
+	}
+}
+
+fn mouse_pointer(
+	mut pointer: ResMut<Pointer>,
+	mouse_button: Res<Input<MouseButton>>,
+	mut cursor_moved: EventReader<CursorMoved>,
+	windows: Res<Windows>,
+	view: Res<View>,
+	cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+) {
+	pointer.just_pressed = false;
+	pointer.just_released = false;
+	pointer.just_long_pressed = false;
+
+	// a mouse that sits still leaves the pointer to the shop cursor
+	let moved = cursor_moved.iter().count() > 0;
+	let clicked = mouse_button.just_pressed(MouseButton::Left);
+	let released = mouse_button.just_released(MouseButton::Left);
+	if !(moved || clicked || released) {
+		return;
+	}
+	let position = match cursor_world_position(&windows, &view, &cameras) {
+		Some(position) => position,
+		None => return,
+	};
+	pointer.position = Some(position);
+	pointer.reach = 0.0;
+	pointer.hovering = true;
+	if clicked {
+		pointer.press(position);
+	}
+	if released {
+		pointer.release(position);
+	}
+}
+
+struct HeldTouch {
+	id: u64,
+	held_for: f32,
+}
+
+// the first finger down plays, others are ignored until it lifts
+fn touch_pointer(
+	mut pointer: ResMut<Pointer>,
+	mut held: Local<Option<HeldTouch>>,
+	time: Res<Time>,
+	touches: Res<Touches>,
+	windows: Res<Windows>,
+	view: Res<View>,
+	cameras: Query<(&Camera, &GlobalTransform, &OrthographicProjection), With<Camera2d>>,
+) {
+	let window = match windows.get_primary() {
+		Some(window) => window,
+		None => return,
+	};
+	let (camera, camera_transform, projection) = cameras.single();
+	let window_size = Vec2::new(window.width(), window.height());
+	// bevy only flips touches on mobile, elsewhere they're measured from the top like winit's
+	let to_screen = |mut position: Vec2| {
+		if !cfg!(any(target_os = "android", target_os = "ios")) {
+			position.y = window_size.y - position.y;
+		}
+		position
+	};
+	let to_world = |position: Vec2| {
+		screen_to_world(window_size, to_screen(position), camera, camera_transform)
+	};
+
+	let id = match &*held {
+		Some(touch) => touch.id,
+		None => {
+			// touches on the letterbox are ignored
+			let in_view = |touch: &&Touch| view.contains(to_screen(touch.position()));
+			if let Some(touch) = touches.iter_just_pressed().find(in_view) {
+				*held = Some(HeldTouch { id: touch.id(), held_for: 0.0 });
+				pointer.reach = TOUCH_REACH * projection.scale;
+				pointer.hovering = false;
+				pointer.press(to_world(touch.position()));
+			}
+			return;
+		},
+	};
+
+	// a cancelled touch (the system took over the gesture) ends like a lifted finger
+	let lifted = touches.get_released(id);
+	if let Some(touch) = lifted.or_else(|| touches.iter_just_cancelled().find(|t| t.id() == id)) {
+		pointer.release(to_world(touch.position()));
+		pointer.inspecting = false;
+		*held = None;
+		return;
+	}
+
+	if let (Some(touch), Some(held)) = (touches.get_pressed(id), held.as_mut()) {
+		pointer.position = Some(to_world(touch.position()));
+		let before = held.held_for;
+		held.held_for += time.delta_seconds();
+		let still = touch.distance().length() < TOUCH_REACH;
+		if still && before < LONG_PRESS && held.held_for >= LONG_PRESS {
+			pointer.just_long_pressed = true;
+			pointer.inspecting = true;
+		}
+	}
+}
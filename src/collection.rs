@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+
+use crate::{
+	connect::{Collection, Tokens},
+	game::buddy::BuddyFace,
+	menu::{back_to_menu, BackButton},
+	widget::spawn_button,
+	AppState,
+};
+
+pub struct CollectionPlugin;
+
+impl Plugin for CollectionPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_system_set(SystemSet::on_enter(AppState::Collection).with_system(spawn_collection))
+			.add_system_set(SystemSet::on_update(AppState::Collection).with_system(back_to_menu))
+			.add_system_set(
+				SystemSet::on_exit(AppState::Collection).with_system(cleanup_collection),
+			);
+	}
+}
+
+struct CollectionData {
+	root: Entity,
+}
+
+const CARD_SIZE: f32 = 120.0;
+
+fn spawn_collection(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	collection: Res<Collection>,
+	tokens: Res<Tokens>,
+) {
+	let text_style = |font_size| TextStyle {
+		font: asset_server.load("font/CaveatBrush-Regular.ttf"),
+		font_size,
+		color: Color::hex("323232").unwrap(),
+	};
+
+	let root = commands
+		.spawn_bundle(NodeBundle {
+			style: Style {
+				size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+				// ui is laid out bottom to top, so the title comes first
+				flex_direction: FlexDirection::ColumnReverse,
+				justify_content: JustifyContent::Center,
+				align_items: AlignItems::Center,
+				..Default::default()
+			},
+			color: Color::rgba(1.0, 1.0, 1.0, 0.8).into(),
+			..Default::default()
+		})
+		.with_children(|parent| {
+			parent.spawn_bundle(TextBundle {
+				text: Text::with_section("Collection", text_style(90.0), Default::default()),
+				..Default::default()
+			});
+			if collection.0.is_empty() {
+				parent.spawn_bundle(TextBundle {
+					text: Text::with_section(
+						"No buddies collected yet",
+						text_style(40.0),
+						Default::default(),
+					),
+					..Default::default()
+				});
+			}
+			parent
+				.spawn_bundle(NodeBundle {
+					style: Style {
+						max_size: Size::new(Val::Percent(80.0), Val::Undefined),
+						flex_wrap: FlexWrap::Wrap,
+						justify_content: JustifyContent::Center,
+						..Default::default()
+					},
+					color: Color::NONE.into(),
+					..Default::default()
+				})
+				.with_children(|parent| {
+					for (index, character) in collection.0.iter().enumerate() {
+						let face = BuddyFace::from_index(character.face);
+						let color = &character.color;
+						parent
+							.spawn_bundle(NodeBundle {
+								style: Style {
+									flex_direction: FlexDirection::ColumnReverse,
+									align_items: AlignItems::Center,
+									margin: Rect::all(Val::Px(15.0)),
+									..Default::default()
+								},
+								color: Color::NONE.into(),
+								..Default::default()
+							})
+							.with_children(|parent| {
+								parent
+									.spawn_bundle(ImageBundle {
+										style: Style {
+											size: Size::new(Val::Px(CARD_SIZE), Val::Px(CARD_SIZE)),
+											..Default::default()
+										},
+										image: UiImage(asset_server.load("buddy/base.png")),
+										color: Color::rgb_u8(color.r, color.g, color.b).into(),
+										..Default::default()
+									})
+									.with_children(|parent| {
+										parent.spawn_bundle(ImageBundle {
+											style: Style {
+												size: Size::new(
+													Val::Percent(100.0),
+													Val::Percent(100.0),
+												),
+												..Default::default()
+											},
+											image: UiImage(asset_server.load(face.get_path())),
+											..Default::default()
+										});
+									});
+								parent.spawn_bundle(TextBundle {
+									text: Text::with_section(
+										format!("{} / {}", character.health, character.strength),
+										text_style(35.0),
+										Default::default(),
+									),
+									..Default::default()
+								});
+								if let Some(token_id) = tokens.get(index) {
+									parent.spawn_bundle(TextBundle {
+										text: Text::with_section(
+											format!("#{}", token_id),
+											text_style(25.0),
+											Default::default(),
+										),
+										..Default::default()
+									});
+								}
+							});
+					}
+				});
+			spawn_button(parent, &asset_server, "Back", 150.0, BackButton);
+		})
+		.id();
+
+	commands.insert_resource(CollectionData { root });
+}
+
+fn cleanup_collection(mut commands: Commands, collection: Res<CollectionData>) {
+	commands.entity(collection.root).despawn_recursive();
+}
@@ -1,7 +1,8 @@
 use bevy::prelude::*;
 
 use crate::game::counters::{Coins, Trophies};
-use crate::network::Network;
+use crate::network::{Network, NetworkEvent};
+use crate::types::CharacterDetails;
 use crate::types::NetworkMessage;
 use crate::types::NetworkMessageResponse;
 use crate::AppState;
@@ -11,11 +12,33 @@ pub struct ConnectPlugin;
 impl Plugin for ConnectPlugin {
 	fn build(&self, app: &mut App) {
 		app.insert_resource(ConnectInformation::new())
+			.init_resource::<Collection>()
+			.init_resource::<Tokens>()
 			.add_system_set(SystemSet::on_enter(AppState::Connect).with_system(enter_state))
+			.add_system_set(SystemSet::on_update(AppState::Connect).with_system(receive_tokens))
 			.add_system_set(SystemSet::on_exit(AppState::Connect).with_system(exit_state));
 	}
 }
 
+/// The buddies this account owns, as the server sent them when connecting
+#[derive(Default)]
+pub struct Collection(pub Vec<CharacterDetails>);
+
+/// The token id of each buddy in the `Collection`, empty until the server answers
+#[derive(Default)]
+pub struct Tokens(pub Vec<Option<String>>);
+
+impl Tokens {
+	pub fn get(&self, index: usize) -> Option<&str> {
+		self.0.get(index).and_then(|token| token.as_deref())
+	}
+}
+
+// servers without tokens never answer, so stop waiting after this many seconds
+const TOKEN_TIMEOUT: f32 = 2.0;
+
+struct TokenFetch(Timer);
+
 pub struct ConnectInformation {
 	account_seed: Option<String>,
 }
@@ -27,11 +50,13 @@ impl ConnectInformation {
 }
 
 pub fn enter_state(
+	mut commands: Commands,
 	mut state: ResMut<State<AppState>>,
 	mut _conn_info: ResMut<ConnectInformation>,
 	mut net: ResMut<Network>,
 	mut coins: ResMut<Coins>,
 	mut trophies: ResMut<Trophies>,
+	mut collection: ResMut<Collection>,
 ) {
 	/* 	let mut account_seed = String::new();
 	println!("Enter your Account Seed: ");
@@ -44,15 +69,54 @@ pub fn enter_state(
 	net.send_message(&msg);
 
 	let response = net.read_message().unwrap();
-	match response {
-		NetworkMessageResponse::Connect(user_data, _) => {
-			*coins = Coins(user_data.coins as usize);
-			trophies.won = user_data.trophies.0 as usize;
-			trophies.rounds = user_data.trophies.1 as usize;
+	// anything else isn't an answer to connecting
+	if let NetworkMessageResponse::Connect(user_data, characters) = response {
+		*coins = Coins(user_data.coins as usize);
+		trophies.won = user_data.trophies.0 as usize;
+		trophies.rounds = user_data.trophies.1 as usize;
+		collection.0 = characters;
+	}
+
+	match net.try_send_message(&NetworkMessage::FetchTokens) {
+		Ok(()) => commands.insert_resource(TokenFetch(Timer::from_seconds(TOKEN_TIMEOUT, false))),
+		Err(err) => {
+			warn!("Couldn't ask for tokens: {}", err);
+			state.set(AppState::Menu).unwrap();
 		},
 	}
+}
 
-	state.set(AppState::Menu).unwrap();
+fn receive_tokens(
+	mut commands: Commands,
+	time: Res<Time>,
+	mut state: ResMut<State<AppState>>,
+	mut events: EventReader<NetworkEvent>,
+	mut fetch: Option<ResMut<TokenFetch>>,
+	mut tokens: ResMut<Tokens>,
+) {
+	let fetch = match fetch.as_mut() {
+		Some(fetch) => fetch,
+		None => return,
+	};
+	let mut done = false;
+	for event in events.iter() {
+		match event {
+			NetworkEvent::Response(NetworkMessageResponse::Tokens(ids)) => {
+				tokens.0 = ids.clone();
+				done = true;
+			},
+			NetworkEvent::Disconnected(err) => {
+				warn!("Couldn't fetch tokens: {}", err);
+				done = true;
+			},
+			// answers to someone else
+			NetworkEvent::Response(_) => {},
+		}
+	}
+	if done || fetch.0.tick(time.delta()).finished() {
+		commands.remove_resource::<TokenFetch>();
+		state.set(AppState::Menu).unwrap();
+	}
 }
 
 pub fn exit_state() {}
@@ -0,0 +1,149 @@
+use crate::game::buddy::TeamSize;
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+// designers can edit assets/balance.json without rebuilding, builds that can't read files (like
+// wasm) fall back to the copy that was embedded at compile time
+const BALANCE_PATH: &str = "assets/balance.json";
+const DEFAULT_BALANCE: &str = include_str!("../../assets/balance.json");
+
+#[derive(Deserialize)]
+pub struct BalanceConfig {
+	pub price: PriceConfig,
+	pub run: RunConfig,
+	pub rewards: RewardConfig,
+	pub item_price: usize,
+	pub reroll_cost: usize,
+	pub team_size: TeamSizeConfig,
+	pub tiers: Vec<ShopTier>,
+}
+
+#[derive(Deserialize)]
+pub struct RunConfig {
+	pub starting_coins: usize,
+	/// Defeats allowed before the run is over
+	pub lives: usize,
+	/// Wins needed to finish the run victorious
+	pub trophy_target: usize,
+}
+
+/// Coins paid out after each battle
+#[derive(Deserialize)]
+pub struct RewardConfig {
+	pub win: usize,
+	pub lose: usize,
+	pub tie: usize,
+}
+
+#[derive(Deserialize)]
+pub struct TeamSizeConfig {
+	pub start: usize,
+	pub max: usize,
+	/// The team gets another slot every this many rounds, 0 keeps it at `start`
+	pub grow_every: usize,
+}
+
+#[derive(Deserialize)]
+pub struct PriceConfig {
+	pub per_stat_point: f32,
+	pub min: usize,
+	pub max: usize,
+}
+
+#[derive(Deserialize)]
+pub struct ShopTier {
+	pub name: String,
+	/// The first round (counted in battles played) this tier can show up in the shop
+	pub unlock_round: usize,
+	/// Relative chance of rolling this tier among all unlocked tiers
+	pub weight: u32,
+	pub min_stat: usize,
+	pub max_stat: usize,
+}
+
+impl Default for BalanceConfig {
+	fn default() -> Self {
+		let from_disk = if cfg!(target_arch = "wasm32") {
+			None
+		} else {
+			std::fs::read_to_string(BALANCE_PATH).ok()
+		};
+
+		if let Some(json) = from_disk {
+			match serde_json::from_str::<BalanceConfig>(&json).map_err(|err| err.to_string()) {
+				Ok(config) => match config.validate() {
+					Ok(()) => return config,
+					Err(err) => error!("invalid {}, using built in balance: {}", BALANCE_PATH, err),
+				},
+				Err(err) => {
+					error!("failed to parse {}, using built in balance: {}", BALANCE_PATH, err)
+				},
+			}
+		}
+
+		serde_json::from_str(DEFAULT_BALANCE).expect("built in balance config is valid")
+	}
+}
+
+impl BalanceConfig {
+	fn validate(&self) -> Result<(), String> {
+		if self.run.lives == 0 || self.run.trophy_target == 0 {
+			return Err("a run needs at least one life and one trophy to win".to_string());
+		}
+		let sizes = TeamSize::MIN..=TeamSize::MAX;
+		if !sizes.contains(&self.team_size.start) || !sizes.contains(&self.team_size.max) {
+			return Err(format!(
+				"team sizes must be between {} and {}",
+				TeamSize::MIN,
+				TeamSize::MAX
+			));
+		}
+		if self.team_size.max < self.team_size.start {
+			return Err("team_size.max can't be below team_size.start".to_string());
+		}
+		if self.price.min > self.price.max {
+			return Err("price.min can't be above price.max".to_string());
+		}
+		match self.tiers.first() {
+			Some(tier) if tier.unlock_round == 0 => {},
+			_ => return Err("the first tier must unlock on round 0".to_string()),
+		}
+		for tier in self.tiers.iter() {
+			if tier.min_stat == 0 || tier.min_stat > tier.max_stat {
+				return Err(format!("tier {} needs 0 < min_stat <= max_stat", tier.name));
+			}
+		}
+		Ok(())
+	}
+
+	/// Price of a buddy with the given `base_cost` (the sum of its base stats)
+	pub fn price(&self, base_cost: usize) -> usize {
+		let price = (base_cost as f32 * self.price.per_stat_point).round() as usize;
+		price.clamp(self.price.min, self.price.max)
+	}
+
+	pub fn team_size(&self, round: usize) -> usize {
+		let TeamSizeConfig { start, max, grow_every } = self.team_size;
+		let grown = round.checked_div(grow_every).unwrap_or(0);
+		(start + grown).min(max)
+	}
+
+	/// Picks one of the tiers unlocked by `round`, weighted by rarity
+	pub fn roll_tier(&self, rng: &mut impl Rng, round: usize) -> &ShopTier {
+		let unlocked = self.tiers.iter().filter(|tier| tier.unlock_round <= round);
+		let total_weight: u32 = unlocked.clone().map(|tier| tier.weight).sum();
+		if total_weight == 0 {
+			return &self.tiers[0];
+		}
+
+		let mut roll = rng.gen_range(0..total_weight);
+		for tier in unlocked {
+			if roll < tier.weight {
+				return tier;
+			}
+			roll -= tier.weight;
+		}
+		unreachable!("roll is always below the total weight")
+	}
+}
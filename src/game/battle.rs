@@ -2,35 +2,54 @@ use std::time::Duration;
 
 use crate::{
 	game::{
-		animate::{AnimateRange, Ease},
-		buddy::{Buddy, BuddyTemplate, Health, Offset, Side, Slot, Strength},
-		counters::{Coins, Trophies},
+		balance::{BalanceConfig, RewardConfig},
+		buddy::{
+			Buddy, BuddyColor, BuddyFace, Health, Level, Offset, Side, Slot, Strength, TeamSize,
+		},
+		counters::{Coins, RunProgress, Trophies},
+		daily::{daily_budget, DailyChallenge},
+		item::{block_hit, FaintBuff, Shielded},
+		opponent::{build_opponent, Difficulty},
 		pad::{pad_enter_battle, pad_exit_battle, position_pad, PAD_SPACING},
+		replay::{spawn_recorded_buddy, BattleLog, BattleOutcome, BattleStep, BuddyRecord},
+		rng::GameRng,
+		run::run_over,
+		ui::UiRoot,
 		BattleMessages,
 	},
+	settings::Settings,
+	tween::{Ease, ScaleLens, Sequence, TranslationLens, Tween, Tweenable},
+	widget::{spawn_game_button, ButtonPressed, Selected},
 	AppState,
 };
 use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+use std::collections::VecDeque;
 
 pub struct BattlePlugin;
 
 impl Plugin for BattlePlugin {
 	fn build(&self, app: &mut App) {
-		app.init_resource::<Battle>()
+		app.add_event::<BattleEvent>()
+			.init_resource::<Battle>()
+			.init_resource::<BattleSpeed>()
 			.add_system_set(
 				SystemSet::on_enter(AppState::Battle)
 					.with_system(pad_enter_battle)
-					.with_system(enter_battle),
+					.with_system(enter_battle)
+					.with_system(spawn_speed_buttons),
 			)
 			.add_system_set(
 				SystemSet::on_update(AppState::Battle)
 					.with_system(battle)
-					.with_system(position_pad),
+					.with_system(position_pad)
+					.with_system(speed_buttons),
 			)
 			.add_system_set(
 				SystemSet::on_exit(AppState::Battle)
 					.with_system(pad_exit_battle)
-					.with_system(exit_battle),
+					.with_system(exit_battle)
+					.with_system(despawn_speed_buttons),
 			);
 	}
 }
@@ -43,38 +62,320 @@ pub enum Action {
 	ExecuteAttack {
 		left_buddy: Entity,
 		right_buddy: Entity,
-		left_strength: usize,
-		right_strength: usize,
 		left_died: bool,
 		right_died: bool,
-		animate_in: AnimateRange,
-		animate_out: AnimateRange,
+		/// The left buddy's lunge, the right one mirrors it
+		lunge_in: Tween<Transform>,
+		lunge_out: Tween<Transform>,
+		/// The hits are dealt once the lunge in is done
+		landed: bool,
 	},
 	Shift {
-		animate_shift: AnimateRange,
 		left_buddy: Entity,
 		right_buddy: Entity,
 		left_died: bool,
 		right_died: bool,
+		/// Moves the buddies behind a fainted one up a slot, mirrored on the right
+		slide: Tween<Transform>,
+		/// Shrinks the fainted buddy away at the same time
+		shrink: Tween<Transform>,
 	},
 	ShowMessage {
 		entity: Entity,
-		animate_in: AnimateRange,
-		animate_out: AnimateRange,
+		popup: Sequence<Transform>,
 	},
 	RestoreBuddies {
-		animate: AnimateRange,
+		timer: Timer,
 	},
 }
 
+impl Action {
+	fn shows_result(&self) -> bool {
+		matches!(self, Action::ShowMessage { .. } | Action::RestoreBuddies { .. })
+	}
+}
+
 impl Default for Battle {
 	fn default() -> Self {
-		Self { action: Action::Begin { timer: Timer::default() } }
+		Self {
+			action: Action::Begin { timer: Timer::default() },
+			log: BattleLog::default(),
+			mode: BattleMode::Live,
+			next_step: 0,
+			skip: false,
+		}
 	}
 }
 
 pub struct Battle {
 	action: Action,
+	pub log: BattleLog,
+	pub mode: BattleMode,
+	/// Index of the next step in `log` to play
+	next_step: usize,
+	/// Resolves the rest of the fight on the next update and jumps straight to the result
+	pub skip: bool,
+}
+
+impl Battle {
+	/// Starts over from the opening pause, playing back the steps in `log`
+	pub fn begin(&mut self, log: BattleLog, mode: BattleMode) {
+		self.action = Action::Begin { timer: Timer::from_seconds(2.0, false) };
+		self.log = log;
+		self.mode = mode;
+		self.next_step = 0;
+		self.skip = false;
+	}
+
+	/// The outcome is decided and showing, there is nothing left to skip
+	pub fn finished(&self) -> bool {
+		self.action.shows_result()
+	}
+}
+
+/// Both play the steps in the log, only live battles pay out
+pub enum BattleMode {
+	/// The log was just worked out by `fight` for this battle
+	Live,
+	/// The log was recorded earlier
+	Replay,
+}
+
+// how far the front buddies lunge at each other to attack
+const LUNGE: f32 = 40.0;
+
+// long enough to finish any action in a single tick
+const SKIP_DELTA: Duration = Duration::from_secs(60);
+
+/// How fast battles play out. Replays can also be paused and stepped one action at a time.
+pub struct BattleSpeed {
+	pub multiplier: f32,
+	pub paused: bool,
+	/// Plays until the next action starts, even while paused
+	pub step: bool,
+}
+
+impl Default for BattleSpeed {
+	fn default() -> Self {
+		Self { multiplier: 1.0, paused: false, step: false }
+	}
+}
+
+impl BattleSpeed {
+	pub fn delta(&self, delta: Duration) -> Duration {
+		if self.paused && !self.step {
+			Duration::ZERO
+		} else {
+			delta.mul_f32(self.multiplier)
+		}
+	}
+}
+
+/// Sent by the `battle` system as the fight plays out, both live and in replays
+pub enum BattleEvent {
+	Hit {
+		attacker: Entity,
+		target: Entity,
+		damage: usize,
+		blocked: bool,
+	},
+	Fainted {
+		buddy: Entity,
+	},
+	/// An on-faint effect buffed the buddy that moved up to the front
+	Buffed {
+		buddy: Entity,
+		health: usize,
+		strength: usize,
+	},
+	Finished(BattleOutcome),
+}
+
+#[derive(Component, Clone, Copy)]
+pub enum SpeedButton {
+	Speed(f32),
+	Skip,
+}
+
+impl SpeedButton {
+	const BUTTONS: [SpeedButton; 4] = [
+		SpeedButton::Speed(1.0),
+		SpeedButton::Speed(2.0),
+		SpeedButton::Speed(4.0),
+		SpeedButton::Skip,
+	];
+
+	fn label(&self) -> String {
+		match self {
+			SpeedButton::Speed(multiplier) => format!("{}x", multiplier),
+			SpeedButton::Skip => "Skip".to_string(),
+		}
+	}
+}
+
+pub fn spawn_speed_buttons(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	ui_root: Query<Entity, With<UiRoot>>,
+) {
+	commands.entity(ui_root.single()).with_children(|parent| {
+		parent
+			.spawn_bundle(NodeBundle {
+				style: Style {
+					position_type: PositionType::Absolute,
+					position: Rect {
+						right: Val::Px(100.0),
+						bottom: Val::Px(40.0),
+						..Default::default()
+					},
+					..Default::default()
+				},
+				color: Color::NONE.into(),
+				..Default::default()
+			})
+			.insert(SpeedButtons)
+			.with_children(|parent| {
+				for button in SpeedButton::BUTTONS {
+					let style = Style {
+						size: Size::new(Val::Px(90.0), Val::Px(65.0)),
+						margin: Rect { left: Val::Px(10.0), ..Default::default() },
+						..Default::default()
+					};
+					spawn_game_button(parent, &asset_server, button.label(), style, button);
+				}
+			});
+	});
+}
+
+#[derive(Component)]
+pub struct SpeedButtons;
+
+pub fn despawn_speed_buttons(mut commands: Commands, rows: Query<Entity, With<SpeedButtons>>) {
+	for entity in rows.iter() {
+		commands.entity(entity).despawn_recursive();
+	}
+}
+
+pub fn speed_buttons(
+	mut commands: Commands,
+	mut battle: ResMut<Battle>,
+	mut speed: ResMut<BattleSpeed>,
+	mut pressed: EventReader<ButtonPressed>,
+	mut buttons: Query<(Entity, &SpeedButton, &mut Style, Option<&Selected>)>,
+) {
+	for ButtonPressed(button) in pressed.iter() {
+		match buttons.get_component::<SpeedButton>(*button) {
+			Ok(SpeedButton::Speed(multiplier)) => speed.multiplier = *multiplier,
+			Ok(SpeedButton::Skip) if !battle.finished() => battle.skip = true,
+			_ => {},
+		}
+	}
+
+	// the current speed stays highlighted, skip goes away once the result is in
+	for (entity, button, mut style, selected) in buttons.iter_mut() {
+		if let SpeedButton::Skip = button {
+			let display = if battle.finished() { Display::None } else { Display::Flex };
+			if style.display != display {
+				style.display = display;
+			}
+		}
+		let is_selected =
+			matches!(button, SpeedButton::Speed(multiplier) if *multiplier == speed.multiplier);
+		match (is_selected, selected.is_some()) {
+			(true, false) => {
+				commands.entity(entity).insert(Selected);
+			},
+			(false, true) => {
+				commands.entity(entity).remove::<Selected>();
+			},
+			_ => {},
+		}
+	}
+}
+
+fn next_step(log: &BattleLog, next_step: &mut usize) -> Option<BattleStep> {
+	let step = log.steps.get(*next_step).cloned();
+	*next_step += 1;
+	step
+}
+
+/// A buddy as the rules see it during a fight
+struct Fighter {
+	slot: usize,
+	health: usize,
+	strength: usize,
+	shielded: bool,
+	faint_buff: Option<FaintBuff>,
+}
+
+impl From<&BuddyRecord> for Fighter {
+	fn from(record: &BuddyRecord) -> Self {
+		Self {
+			slot: record.slot,
+			health: record.health,
+			strength: record.strength,
+			shielded: record.shielded,
+			faint_buff: record.faint_buff,
+		}
+	}
+}
+
+/// A battle as `fight` worked it out
+pub struct Fight {
+	/// Every step, ending with the result
+	pub steps: Vec<BattleStep>,
+	pub outcome: BattleOutcome,
+	/// Slots of the left buddies whose shields were used up
+	pub spent_shields: Vec<usize>,
+}
+
+/// The rules of battle, played out without showing anything. The front buddies trade hits at the
+/// same time, a shield blocks one whole hit, and a buddy that faints hands its faint buff to the
+/// buddy that moves up. The `battle` system shows the steps, the simulation and opponents only
+/// look at the outcome.
+pub fn fight(left: &[BuddyRecord], right: &[BuddyRecord]) -> Fight {
+	let team = |records: &[BuddyRecord]| {
+		let mut records = records.iter().collect::<Vec<_>>();
+		records.sort_by_key(|record| record.slot);
+		records.into_iter().map(Fighter::from).collect::<VecDeque<_>>()
+	};
+	let mut left = team(left);
+	let mut right = team(right);
+	let mut steps = Vec::new();
+	let mut spent_shields = Vec::new();
+
+	while let (Some(left_front), Some(right_front)) = (left.front_mut(), right.front_mut()) {
+		let (left_damage, right_damage) = (right_front.strength, left_front.strength);
+		let (left_blocked, right_blocked) = (left_front.shielded, right_front.shielded);
+		for (front, damage, blocked) in
+			[(left_front, left_damage, left_blocked), (right_front, right_damage, right_blocked)]
+		{
+			if blocked {
+				front.shielded = false;
+			} else {
+				front.health = front.health.saturating_sub(damage);
+			}
+		}
+		if left_blocked {
+			spent_shields.push(left.front().unwrap().slot);
+		}
+		steps.push(BattleStep::Attack { left_damage, right_damage, left_blocked, right_blocked });
+
+		for (team, side) in [(&mut left, Side::Left), (&mut right, Side::Right)] {
+			if team.front().unwrap().health == 0 {
+				let fainted = team.pop_front().unwrap();
+				if let (Some(buff), Some(next)) = (fainted.faint_buff, team.front_mut()) {
+					next.health += buff.health;
+					next.strength += buff.strength;
+				}
+				steps.push(BattleStep::Faint { side, buff: fainted.faint_buff });
+			}
+		}
+	}
+
+	let outcome = BattleOutcome::new(!left.is_empty(), !right.is_empty());
+	steps.push(BattleStep::Result(outcome));
+	Fight { steps, outcome, spent_shields }
 }
 
 pub fn enter_battle(
@@ -82,8 +383,34 @@ pub fn enter_battle(
 	asset_server: Res<AssetServer>,
 	mut battle: ResMut<Battle>,
 	mut trophies: ResMut<Trophies>,
+	balance: Res<BalanceConfig>,
+	settings: Res<Settings>,
+	game_rng: Res<GameRng>,
+	daily: Res<DailyChallenge>,
+	mut run: ResMut<RunProgress>,
+	team_size: Res<TeamSize>,
 	buddies: Query<(Entity, &Side), With<Buddy>>,
+	team: Query<
+		(
+			&Side,
+			&Slot,
+			&BuddyFace,
+			&BuddyColor,
+			&Health,
+			&Strength,
+			&Level,
+			Option<&Shielded>,
+			Option<&FaintBuff>,
+		),
+		With<Buddy>,
+	>,
 ) {
+	// the opponent gets to spend as much as the player has so far
+	let (difficulty, budget) = match daily.0 {
+		Some(_) => (Difficulty::Normal, daily_budget(&balance, run.rounds)),
+		None => (settings.difficulty, run.spent),
+	};
+	run.rounds += 1;
 	trophies.rounds += 1;
 	// clean up old battle entities
 	for (entity, side) in buddies.iter() {
@@ -92,12 +419,37 @@ pub fn enter_battle(
 		}
 	}
 
-	for i in 0..Slot::MAX_PER_SIDE {
-		let template = BuddyTemplate::random_for_round(trophies.rounds);
-		template.spawn(&mut commands, &asset_server, i, Side::Right, Transform::default());
+	let mut left = Vec::new();
+	for (side, slot, face, color, health, strength, level, shielded, faint_buff) in team.iter() {
+		if *side == Side::Left {
+			left.push(BuddyRecord {
+				slot: slot.current,
+				face: *face,
+				color: *color,
+				health: health.0.value(),
+				strength: strength.0.value(),
+				level: level.level,
+				experience: level.experience,
+				shielded: shielded.is_some(),
+				faint_buff: faint_buff.copied(),
+			});
+		}
 	}
+	left.sort_by_key(|record| record.slot);
 
-	battle.action = Action::Begin { timer: Timer::from_seconds(2.0, false) };
+	// opponents come from a seeded rng so the log can tell where they came from
+	let seed = game_rng.battle_seed(run.rounds);
+	let mut rng = StdRng::seed_from_u64(seed);
+	let right =
+		build_opponent(&mut rng, &balance, difficulty, run.rounds, team_size.0, budget, &left);
+	for record in right.iter() {
+		spawn_recorded_buddy(&mut commands, &asset_server, record, Side::Right);
+	}
+
+	// the whole fight is worked out up front and then shown step by step, like a replay
+	let steps = fight(&left, &right).steps;
+	let log = BattleLog { seed, round: run.rounds, left, right, steps };
+	battle.begin(log, BattleMode::Live);
 }
 
 pub fn exit_battle(mut commands: Commands, buddies: Query<(Entity, &Side), With<Buddy>>) {
@@ -110,12 +462,17 @@ pub fn exit_battle(mut commands: Commands, buddies: Query<(Entity, &Side), With<
 }
 
 pub fn battle(
+	mut commands: Commands,
 	mut battle: ResMut<Battle>,
 	battle_messages: Res<BattleMessages>,
 	mut state: ResMut<State<AppState>>,
 	mut trophies: ResMut<Trophies>,
+	mut run: ResMut<RunProgress>,
 	mut coins: ResMut<Coins>,
+	balance: Res<BalanceConfig>,
 	time: Res<Time>,
+	mut speed: ResMut<BattleSpeed>,
+	mut battle_events: EventWriter<BattleEvent>,
 	mut buddies: Query<(
 		Entity,
 		&mut Buddy,
@@ -127,243 +484,395 @@ pub fn battle(
 		&mut Slot,
 	)>,
 	mut messages: Query<(&mut Visibility, &mut Transform), Without<Buddy>>,
+	shielded: Query<&Shielded>,
 ) {
-	let mut next_action = None;
-	match &mut battle.action {
-		Action::Begin { timer } => {
-			if timer.tick(time.delta()).just_finished() {
-				next_action = Some(Action::StartAttack)
-			}
-		},
-		Action::StartAttack => {
-			let mut left_buddy = None;
-			let mut left_strength = 0;
-			let mut right_buddy = None;
-			let mut right_strength = 0;
-			// NOTE : get_multiple() would be really nice here
-			for (entity, _, _, strength, _, _, side, slot) in buddies.iter() {
-				if *side == Side::Left && slot.current == 0 {
-					left_buddy = Some(entity);
-					left_strength = strength.0.value();
-				} else if *side == Side::Right && slot.current == 0 {
-					right_buddy = Some(entity);
-					right_strength = strength.0.value();
+	let Battle { action, log, mode, next_step: step_index, skip } = &mut *battle;
+	let delta = if *skip { SKIP_DELTA } else { speed.delta(time.delta()) };
+	// ticks in a row that didn't move on to another action, see the end of the loop
+	let mut idle_ticks = 0;
+	loop {
+		let mut next_action = None;
+		match &mut *action {
+			Action::Begin { timer } => {
+				if timer.tick(delta).just_finished() {
+					next_action = Some(Action::StartAttack)
+				}
+			},
+			Action::StartAttack => {
+				let mut left_buddy = None;
+				let mut right_buddy = None;
+				// NOTE : get_multiple() would be really nice here
+				for (entity, _, _, _, _, _, side, slot) in buddies.iter() {
+					if *side == Side::Left && slot.current == 0 {
+						left_buddy = Some(entity);
+					} else if *side == Side::Right && slot.current == 0 {
+						right_buddy = Some(entity);
+					}
 				}
-			}
 
-			if let (Some(left_buddy), Some(right_buddy)) = (left_buddy, right_buddy) {
-				next_action = Some(Action::ExecuteAttack {
-					left_buddy,
-					right_buddy,
-					left_strength,
-					right_strength,
-					left_died: false,
-					right_died: false,
-					animate_in: AnimateRange::new(
-						Duration::from_secs_f32(0.3),
-						Ease::InOutCirc,
-						0.0..40.0,
-						false,
-					),
-					animate_out: AnimateRange::new(
-						Duration::from_secs_f32(0.3),
-						Ease::InOutCirc,
-						40.0..0.0,
-						false,
-					),
-				});
-			}
-		},
-		Action::ExecuteAttack {
-			left_buddy,
-			right_buddy,
-			left_strength,
-			right_strength,
-			left_died,
-			right_died,
-			animate_in,
-			animate_out,
-		} => {
-			if !animate_in.finished() {
-				let x = animate_in.tick(time.delta());
-				if let Ok(mut offset) = buddies.get_component_mut::<Offset>(*left_buddy) {
-					offset.0.translation = Vec3::new(x, 0.0, 0.0);
+				if let (Some(left_buddy), Some(right_buddy)) = (left_buddy, right_buddy) {
+					next_action = Some(Action::ExecuteAttack {
+						left_buddy,
+						right_buddy,
+						left_died: false,
+						right_died: false,
+						lunge_in: Tween::new(
+							Duration::from_secs_f32(0.3),
+							Ease::InOutCirc,
+							TranslationLens { start: Vec3::ZERO, end: Vec3::X * LUNGE },
+						),
+						lunge_out: Tween::new(
+							Duration::from_secs_f32(0.3),
+							Ease::InOutCirc,
+							TranslationLens { start: Vec3::X * LUNGE, end: Vec3::ZERO },
+						),
+						landed: false,
+					});
+				} else {
+					// a side with nobody left to send out loses straight away
+					next_action = Some(battle_result(
+						left_buddy.is_some(),
+						right_buddy.is_some(),
+						log,
+						step_index,
+						mode,
+						&mut battle_events,
+						&battle_messages,
+						&balance,
+						&mut trophies,
+						&mut run,
+						&mut coins,
+					));
 				}
-				if let Ok(mut offset) = buddies.get_component_mut::<Offset>(*right_buddy) {
-					offset.0.translation = Vec3::new(-x, 0.0, 0.0);
+			},
+			Action::ExecuteAttack {
+				left_buddy,
+				right_buddy,
+				left_died,
+				right_died,
+				lunge_in,
+				lunge_out,
+				landed,
+			} => {
+				let mut lunge = Transform::default();
+				let lunge_done = if *landed {
+					lunge_out.tick(delta, &mut lunge).is_some()
+				} else {
+					lunge_in.tick(delta, &mut lunge).is_some()
+				};
+				for (buddy, sign) in [(*left_buddy, 1.0), (*right_buddy, -1.0)] {
+					if let Ok(mut offset) = buddies.get_component_mut::<Offset>(buddy) {
+						offset.0.translation = lunge.translation * sign;
+					}
 				}
-				if animate_in.just_finished() {
-					if let Ok(mut health) = buddies.get_component_mut::<Health>(*left_buddy) {
-						health.0.remove(*right_strength);
-						*left_died = health.0.value() == 0;
+				if !*landed {
+					if lunge_done {
+						*landed = true;
+						let step = next_step(log, step_index);
+						if let Some(BattleStep::Attack {
+							left_damage,
+							right_damage,
+							left_blocked,
+							right_blocked,
+						}) = step
+						{
+							for (buddy, attacker, damage, blocked, died) in [
+								(
+									*left_buddy,
+									*right_buddy,
+									left_damage,
+									left_blocked,
+									&mut *left_died,
+								),
+								(
+									*right_buddy,
+									*left_buddy,
+									right_damage,
+									right_blocked,
+									&mut *right_died,
+								),
+							] {
+								if let Ok(mut health) = buddies.get_component_mut::<Health>(buddy) {
+									if blocked {
+										block_hit(&mut commands, &shielded, buddy);
+									} else {
+										health.0.remove(damage);
+									}
+									*died = health.0.value() == 0;
+									battle_events.send(BattleEvent::Hit {
+										attacker,
+										target: buddy,
+										damage,
+										blocked,
+									});
+								}
+							}
+						} else {
+							// a log that ran out or doesn't match this battle ends the replay
+							*left_died = true;
+							*right_died = true;
+						}
 					}
-					if let Ok(mut health) = buddies.get_component_mut::<Health>(*right_buddy) {
-						health.0.remove(*left_strength);
-						*right_died = health.0.value() == 0;
+				} else if lunge_done {
+					for (buddy, died) in [(*left_buddy, *left_died), (*right_buddy, *right_died)] {
+						if died {
+							battle_events.send(BattleEvent::Fainted { buddy });
+						}
 					}
-				}
-			} else {
-				let x = animate_out.tick(time.delta());
-				if let Ok(mut offset) = buddies.get_component_mut::<Offset>(*left_buddy) {
-					offset.0.translation = Vec3::new(x, 0.0, 0.0);
-				}
-				if let Ok(mut offset) = buddies.get_component_mut::<Offset>(*right_buddy) {
-					offset.0.translation = Vec3::new(-x, 0.0, 0.0);
-				}
-				if animate_out.finished() {
 					next_action = Some(Action::Shift {
 						left_buddy: *left_buddy,
 						right_buddy: *right_buddy,
 						left_died: *left_died,
 						right_died: *right_died,
-						animate_shift: AnimateRange::new(
+						slide: Tween::new(
 							Duration::from_secs_f32(1.0),
 							Ease::InOutCirc,
-							0.0..(PAD_SPACING),
-							false,
+							TranslationLens {
+								start: Vec3::ZERO,
+								end: Vec3::new(PAD_SPACING, 0.0, 0.0),
+							},
+						),
+						shrink: Tween::new(
+							Duration::from_secs_f32(1.0),
+							Ease::Linear,
+							ScaleLens {
+								start: Vec3::new(1.0, 1.0, 0.9),
+								end: Vec3::new(0.0, 0.0, 0.9),
+							},
 						),
 					})
 				}
-			}
-		},
-		Action::Shift { left_buddy, right_buddy, left_died, right_died, animate_shift } => {
-			let x = animate_shift.tick(time.delta());
-			let percent = animate_shift.percent();
-			for (entity, _, _, _, _, mut offset, side, _) in buddies.iter_mut() {
-				if *left_died && *side == Side::Left {
-					if entity == *left_buddy {
-						offset.0.scale = Vec3::new(1.0 - percent, 1.0 - percent, 0.9);
-					} else {
-						offset.0.translation = Vec3::new(x, 0.0, 0.0);
-					}
-				}
-
-				if *right_died && *side == Side::Right {
-					if entity == *right_buddy {
-						offset.0.scale = Vec3::new(1.0 - percent, 1.0 - percent, 0.9);
-					} else {
-						offset.0.translation = Vec3::new(-x, 0.0, 0.0);
-					}
-				}
-			}
-			if animate_shift.just_finished() {
-				for (entity, mut buddy, _, _, _, mut offset, side, mut slot) in buddies.iter_mut() {
+			},
+			Action::Shift { left_buddy, right_buddy, left_died, right_died, slide, shrink } => {
+				let mut moved = Transform::default();
+				let mut shrunk = Transform::default();
+				let shift_done = slide.tick(delta, &mut moved).is_some();
+				shrink.tick(delta, &mut shrunk);
+				for (entity, _, _, _, _, mut offset, side, _) in buddies.iter_mut() {
 					if *left_died && *side == Side::Left {
 						if entity == *left_buddy {
-							buddy.alive = false;
-							slot.current = 10;
-						} else if buddy.alive {
-							offset.0.translation = Vec3::new(0.0, 0.0, 0.0);
-							slot.current -= 1;
+							offset.0.scale = shrunk.scale;
+						} else {
+							offset.0.translation = moved.translation;
 						}
 					}
 
 					if *right_died && *side == Side::Right {
 						if entity == *right_buddy {
-							buddy.alive = false;
-							slot.current = 10;
-						} else if buddy.alive {
-							offset.0.translation = Vec3::new(0.0, 0.0, 0.0);
-							slot.current -= 1;
+							offset.0.scale = shrunk.scale;
+						} else {
+							offset.0.translation = -moved.translation;
 						}
 					}
 				}
+				if shift_done {
+					for (entity, mut buddy, _, _, _, mut offset, side, mut slot) in
+						buddies.iter_mut()
+					{
+						if *left_died && *side == Side::Left {
+							if entity == *left_buddy {
+								buddy.alive = false;
+								slot.current = 10;
+							} else if buddy.alive {
+								offset.0.translation = Vec3::new(0.0, 0.0, 0.0);
+								slot.current -= 1;
+							}
+						}
 
-				let mut left_alive = false;
-				let mut right_alive = false;
-				for (_, buddy, _, _, _, _, side, _) in buddies.iter() {
-					if *side == Side::Left && buddy.alive {
-						left_alive = true;
-					}
-					if *side == Side::Right && buddy.alive {
-						right_alive = true;
+						if *right_died && *side == Side::Right {
+							if entity == *right_buddy {
+								buddy.alive = false;
+								slot.current = 10;
+							} else if buddy.alive {
+								offset.0.translation = Vec3::new(0.0, 0.0, 0.0);
+								slot.current -= 1;
+							}
+						}
 					}
-				}
 
-				let animate_in =
-					AnimateRange::new(Duration::from_secs_f32(1.0), Ease::OutBack, 0.0..1.0, false);
-				let animate_out = AnimateRange::new(
-					Duration::from_secs_f32(0.6),
-					Ease::InOutCirc,
-					1.0..0.0,
-					false,
-				);
-				let action = match (left_alive, right_alive) {
-					(true, true) => Action::StartAttack,
-					(true, false) => {
-						trophies.won += 1;
-						coins.0 += 5;
-						Action::ShowMessage {
-							entity: battle_messages.you_win,
-							animate_in,
-							animate_out,
+					// on-faint effects go to whoever shifted into the front slot
+					for (died, dead_side) in [(*left_died, Side::Left), (*right_died, Side::Right)]
+					{
+						if !died {
+							continue;
 						}
-					},
-					(false, true) => {
-						coins.0 += 2;
-						Action::ShowMessage {
-							entity: battle_messages.you_lose,
-							animate_in,
-							animate_out,
+						let step = next_step(log, step_index);
+						if let Some(BattleStep::Faint { buff: Some(buff), .. }) = step {
+							for (entity, buddy, mut health, mut strength, _, _, side, slot) in
+								buddies.iter_mut()
+							{
+								if *side == dead_side && buddy.alive && slot.current == 0 {
+									health.0.add(buff.health);
+									strength.0.add(buff.strength);
+									battle_events.send(BattleEvent::Buffed {
+										buddy: entity,
+										health: buff.health,
+										strength: buff.strength,
+									});
+								}
+							}
 						}
-					},
-					(false, false) => {
-						coins.0 += 3;
-						Action::ShowMessage {
-							entity: battle_messages.you_tie,
-							animate_in,
-							animate_out,
+					}
+
+					let mut left_alive = false;
+					let mut right_alive = false;
+					for (_, buddy, _, _, _, _, side, _) in buddies.iter() {
+						if *side == Side::Left && buddy.alive {
+							left_alive = true;
+						}
+						if *side == Side::Right && buddy.alive {
+							right_alive = true;
 						}
+					}
+
+					let action = match (left_alive, right_alive) {
+						(true, true) => Action::StartAttack,
+						_ => battle_result(
+							left_alive,
+							right_alive,
+							log,
+							step_index,
+							mode,
+							&mut battle_events,
+							&battle_messages,
+							&balance,
+							&mut trophies,
+							&mut run,
+							&mut coins,
+						),
+					};
+					next_action = Some(action);
+				}
+			},
+			Action::ShowMessage { entity, popup } => {
+				// a missing message has nothing to show, so it's done straight away
+				let popup_done = match messages.get_mut(*entity) {
+					Ok((mut visibility, mut transform)) => {
+						let done = popup.tick(delta, &mut transform).is_some();
+						visibility.is_visible = !done;
+						done
 					},
+					Err(_) => true,
 				};
-				next_action = Some(action);
-			}
-		},
-		Action::ShowMessage { entity, animate_in, animate_out } => {
-			let mut visible = true;
-			let x = if !animate_in.finished() {
-				animate_in.tick(time.delta())
-			} else {
-				let x = animate_out.tick(time.delta());
-				if animate_out.just_finished() {
-					next_action = Some(Action::RestoreBuddies {
-						animate: AnimateRange::new(
-							Duration::from_secs_f32(0.5),
-							Ease::InOutCirc,
-							0.0..1.0,
-							false,
-						),
-					});
-					visible = false;
+				if popup_done {
+					next_action =
+						Some(Action::RestoreBuddies { timer: Timer::from_seconds(0.5, false) });
 				}
-				x
-			};
-			if let Ok((mut visibility, mut transform)) = messages.get_mut(*entity) {
-				visibility.is_visible = visible;
-				transform.scale = Vec3::new(x, x, 1.0);
-			}
-		},
-		Action::RestoreBuddies { animate } => {
-			animate.tick(time.delta());
-
-			if animate.just_finished() {
-				for (_, mut buddy, mut health, mut strength, _, mut offset, side, mut slot) in
-					buddies.iter_mut()
-				{
-					if *side == Side::Left {
-						buddy.alive = true;
-						slot.reset();
-						health.0.reset();
-						strength.0.reset();
-						*offset = Offset::default();
+			},
+			Action::RestoreBuddies { timer } => {
+				if timer.tick(delta).just_finished() {
+					for (_, mut buddy, mut health, mut strength, _, mut offset, side, mut slot) in
+						buddies.iter_mut()
+					{
+						if *side == Side::Left {
+							buddy.alive = true;
+							slot.reset();
+							health.0.reset();
+							strength.0.reset();
+							*offset = Offset::default();
+						}
+					}
+					match mode {
+						// replays are watched on top of the shop, exiting early may have popped it already
+						BattleMode::Replay => {
+							state.pop().ok();
+						},
+						BattleMode::Live if run_over(&balance, &run) => {
+							state.set(AppState::RunSummary).unwrap()
+						},
+						BattleMode::Live => state.set(AppState::Shop).unwrap(),
 					}
 				}
-				state.set(AppState::Shop).unwrap();
-			}
+			},
+		}
+		if let Some(next_action) = next_action {
+			*action = next_action;
+			speed.step = false;
+			idle_ticks = 0;
+		} else {
+			idle_ticks += 1;
+		}
+
+		// skipping plays every action back to back within this frame until the result shows up.
+		// Every action is done within two skipped ticks, one that idles twice is waiting on
+		// something else, like the state change `RestoreBuddies` asked for.
+		if !*skip {
+			break;
+		}
+		if action.shows_result() || idle_ticks > 1 {
+			*skip = false;
+			break;
+		}
+	}
+}
+
+/// Counts the outcome towards the run and pays out the coins for it
+pub fn pay_out(
+	rewards: &RewardConfig,
+	outcome: BattleOutcome,
+	run: &mut RunProgress,
+	coins: &mut Coins,
+) {
+	match outcome {
+		BattleOutcome::Win => {
+			run.won += 1;
+			coins.0 += rewards.win;
 		},
+		BattleOutcome::Lose => {
+			run.lives = run.lives.saturating_sub(1);
+			coins.0 += rewards.lose;
+		},
+		BattleOutcome::Tie => coins.0 += rewards.tie,
 	}
+}
 
-	if let Some(next_action) = next_action {
-		battle.action = next_action;
+/// Hands out the rewards for a finished battle and picks the message to show. Replays show the
+/// recorded outcome without paying out again.
+fn battle_result(
+	left_alive: bool,
+	right_alive: bool,
+	log: &BattleLog,
+	step_index: &mut usize,
+	mode: &BattleMode,
+	battle_events: &mut EventWriter<BattleEvent>,
+	battle_messages: &BattleMessages,
+	balance: &BalanceConfig,
+	trophies: &mut Trophies,
+	run: &mut RunProgress,
+	coins: &mut Coins,
+) -> Action {
+	let outcome = BattleOutcome::new(left_alive, right_alive);
+	let outcome = match next_step(log, step_index) {
+		Some(BattleStep::Result(recorded)) => recorded,
+		_ => outcome,
+	};
+	battle_events.send(BattleEvent::Finished(outcome));
+	if let BattleMode::Live = mode {
+		pay_out(&balance.rewards, outcome, run, coins);
+		// the account keeps its own tally across runs
+		if let BattleOutcome::Win = outcome {
+			trophies.won += 1;
+		}
 	}
+
+	// pops up, then shrinks away again
+	let shown = Vec3::ONE;
+	let hidden = Vec3::new(0.0, 0.0, 1.0);
+	let popup = Sequence::new()
+		.then(Tween::new(
+			Duration::from_secs_f32(1.0),
+			Ease::OutBack,
+			ScaleLens { start: hidden, end: shown },
+		))
+		.then(Tween::new(
+			Duration::from_secs_f32(0.6),
+			Ease::InOutCirc,
+			ScaleLens { start: shown, end: hidden },
+		));
+	let entity = match outcome {
+		BattleOutcome::Win => battle_messages.you_win,
+		BattleOutcome::Lose => battle_messages.you_lose,
+		BattleOutcome::Tie => battle_messages.you_tie,
+	};
+	Action::ShowMessage { entity, popup }
 }
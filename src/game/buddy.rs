@@ -1,13 +1,15 @@
 use crate::{
-	game::{
-		animate::{AnimateRange, AnimateScale, Ease},
-		shop::BuddyDragState,
-		Z_BUDDY,
+	game::{balance::ShopTier, drag::SwapHop, pause::unpaused, shop::BuddyDragState, Z_BUDDY},
+	settings::Settings,
+	tween::{
+		Animator, Delay, Ease, Parallel, RotationLens, ScaleLens, Sequence, TranslationLens, Tween,
+		Tweenable,
 	},
 	AppState,
 };
 use bevy::{prelude::*, text::Text2dSize};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::{f32::consts::PI, time::Duration};
 
 pub struct BuddyPlugin;
@@ -15,19 +17,24 @@ pub struct BuddyPlugin;
 impl Plugin for BuddyPlugin {
 	fn build(&self, app: &mut App) {
 		app.init_resource::<OutlineTimer>()
-			.add_system(update_outlines)
-			.add_system(set_buddy_face)
-			.add_system(wobble_buddy)
+			.add_system(update_outlines.with_run_criteria(unpaused))
+			.add_system(set_buddy_face.with_run_criteria(unpaused))
+			.add_system(wobble_buddy.with_run_criteria(unpaused))
 			.add_system(set_health_counter)
 			.add_system(set_strength_counter)
+			.add_system(set_level_counter)
 			.add_system_set(add_buddy_render_systems_to_set(SystemSet::new()))
 			.add_system_set(add_buddy_render_systems_to_set(SystemSet::on_update(AppState::Shop)))
+			.add_system_set(add_buddy_render_systems_to_set(SystemSet::on_update(AppState::Battle)))
 			.add_system_set(add_buddy_render_systems_to_set(SystemSet::on_update(
-				AppState::Battle,
+				AppState::Replay,
 			)));
 	}
 }
 
+// how much later each slot pops in than the one before it
+const POP_IN_STAGGER: Duration = Duration::from_millis(80);
+
 // This is lame, but we need to duplicate "buddy rendering" systems
 // to ensure they run at the appropriate time on "same frame transitions"
 // without doing this for each relevant AppState, spawned buddies
@@ -36,11 +43,12 @@ impl Plugin for BuddyPlugin {
 pub fn add_buddy_render_systems_to_set(set: SystemSet) -> SystemSet {
 	set.with_system(set_health_counter)
 		.with_system(set_strength_counter)
+		.with_system(set_level_counter)
 		.with_system(move_buddy)
 		.with_system(set_buddy_color)
 }
 
-#[derive(Component, PartialEq, Eq)]
+#[derive(Component, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Side {
 	Left,
 	Right,
@@ -60,7 +68,6 @@ pub struct Slot {
 }
 
 impl Slot {
-	pub const MAX_PER_SIDE: usize = 3;
 	pub fn new(slot: usize) -> Self {
 		Self { current: slot, base: slot }
 	}
@@ -76,23 +83,38 @@ impl Default for Slot {
 	}
 }
 
+/// How many buddies each side fields. Set from the balance config as rounds are played.
+pub struct TeamSize(pub usize);
+
+impl TeamSize {
+	pub const MIN: usize = 3;
+	pub const MAX: usize = 7;
+}
+
+impl Default for TeamSize {
+	fn default() -> Self {
+		Self(Self::MIN)
+	}
+}
+
 pub struct BuddyTemplate {
-	face: BuddyFace,
-	health: usize,
-	strength: usize,
-	color: BuddyColor,
+	pub face: BuddyFace,
+	pub health: usize,
+	pub strength: usize,
+	pub color: BuddyColor,
 }
 
 impl BuddyTemplate {
-	pub fn random_for_round(round: usize) -> Self {
-		let mut rng = rand::thread_rng();
-		let strength = rng.gen_range(1..(2 + round));
-		let health = rng.gen_range(1..(2 + round));
-		Self { face: BuddyFace::random(), health, strength, color: BuddyColor::random() }
+	pub fn random_for_tier(rng: &mut impl Rng, tier: &ShopTier) -> Self {
+		let strength = rng.gen_range(tier.min_stat..=tier.max_stat);
+		let health = rng.gen_range(tier.min_stat..=tier.max_stat);
+		Self { face: BuddyFace::random(rng), health, strength, color: BuddyColor::random(rng) }
+	}
+
+	pub fn base_cost(&self) -> usize {
+		self.health + self.strength
 	}
-	// pub fn base_cost(&self) -> usize {
-	//     self.health + self.strength
-	// }
+
 	pub fn spawn(
 		self,
 		commands: &mut Commands,
@@ -113,14 +135,21 @@ impl BuddyTemplate {
 				..Default::default()
 			})
 			.with_children(|parent| {
+				// buddies pop in one after another, down the row
 				parent
-					.spawn_bundle(SpriteBundle::default())
+					.spawn_bundle(SpriteBundle {
+						transform: Transform::from_scale(Vec3::ZERO),
+						..Default::default()
+					})
 					.insert(BuddyWobble::default())
-					.insert(AnimateScale::new(
-						Duration::from_secs_f32(0.6),
-						Ease::OutBack,
-						0.0..1.0,
-						false,
+					.insert(Animator::new(
+						Sequence::new().then(Delay::new(POP_IN_STAGGER * slot as u32)).then(
+							Tween::new(
+								Duration::from_secs_f32(0.6),
+								Ease::OutBack,
+								ScaleLens { start: Vec3::ZERO, end: Vec3::ONE },
+							),
+						),
 					))
 					.with_children(|parent| {
 						parent
@@ -203,6 +232,34 @@ impl BuddyTemplate {
 							})
 							.insert(StrengthCounter);
 					});
+				parent
+					.spawn_bundle(SpriteBundle {
+						transform: Transform::from_xyz(70.0, 55.0, Z_BUDDY + 0.3)
+							.with_scale(Vec3::splat(0.35)),
+						texture: asset_server.load("buddy/level.png"),
+						..Default::default()
+					})
+					.with_children(|parent| {
+						parent
+							.spawn_bundle(Text2dBundle {
+								text: Text::with_section(
+									"1",
+									TextStyle {
+										font: asset_server.load("font/CaveatBrush-Regular.ttf"),
+										font_size: 110.0,
+										color: Color::hex("323232").unwrap(),
+									},
+									TextAlignment {
+										vertical: VerticalAlign::Bottom,
+										horizontal: HorizontalAlign::Left,
+									},
+								),
+								text_2d_size: Text2dSize { size: Size::new(100., 100.) },
+								transform: Transform::from_xyz(-14.0, -62.0, 0.1),
+								..Default::default()
+							})
+							.insert(LevelCounter);
+					});
 			})
 			.id()
 	}
@@ -222,13 +279,20 @@ impl Default for Buddy {
 #[derive(Component, Default)]
 pub struct BuddyOutline;
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BuddyFace {
 	Happy,
 	Neutral,
 }
 
 impl BuddyFace {
+	pub fn name(&self) -> &'static str {
+		match self {
+			BuddyFace::Happy => "Happy",
+			BuddyFace::Neutral => "Neutral",
+		}
+	}
+
 	pub fn get_path(&self) -> &'static str {
 		match self {
 			BuddyFace::Happy => "buddy/face/happy.png",
@@ -236,12 +300,15 @@ impl BuddyFace {
 		}
 	}
 
-	pub fn random() -> BuddyFace {
-		let index = rand::thread_rng().gen_range(0..2);
+	pub fn random(rng: &mut impl Rng) -> BuddyFace {
+		Self::from_index(rng.gen_range(0..2))
+	}
+
+	/// Faces are numbered like in the server's character details, unknown ones look happy
+	pub fn from_index(index: u16) -> BuddyFace {
 		match index {
-			0 => BuddyFace::Happy,
 			1 => BuddyFace::Neutral,
-			_ => panic!("this shouldn't happen"),
+			_ => BuddyFace::Happy,
 		}
 	}
 }
@@ -275,7 +342,7 @@ impl BuddyBlink {
 	}
 }
 
-#[derive(Component, Default, Copy, Clone)]
+#[derive(Component, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BuddyColor(Color);
 
 impl BuddyColor {
@@ -283,8 +350,24 @@ impl BuddyColor {
 	const GREEN: BuddyColor = Self(Color::rgb(0.53, 0.67, 0.53));
 	const BLUE: BuddyColor = Self(Color::rgb(0.53, 0.53, 0.67));
 	const COLORS: &'static [BuddyColor] = &[Self::RED, Self::GREEN, Self::BLUE];
-	pub fn random() -> BuddyColor {
-		Self::COLORS[rand::thread_rng().gen_range(0..Self::COLORS.len())]
+	pub fn random(rng: &mut impl Rng) -> BuddyColor {
+		Self::COLORS[rng.gen_range(0..Self::COLORS.len())]
+	}
+
+	pub fn name(&self) -> &'static str {
+		if *self == Self::RED {
+			"Red"
+		} else if *self == Self::GREEN {
+			"Green"
+		} else if *self == Self::BLUE {
+			"Blue"
+		} else {
+			"Odd"
+		}
+	}
+
+	pub fn color(&self) -> Color {
+		self.0
 	}
 }
 
@@ -316,6 +399,7 @@ pub struct BuddyBundle {
 	pub buddy: Buddy,
 	pub health: Health,
 	pub strength: Strength,
+	pub level: Level,
 	pub face: BuddyFace,
 	pub blink: BuddyBlink,
 	pub position_offset: Offset,
@@ -360,9 +444,15 @@ impl Attribute {
 		Self { base, value: base as isize }
 	}
 
-	// pub fn set_base(&mut self, base: usize) {
-	//     self.base = base;
-	// }
+	pub fn base(&self) -> usize {
+		self.base
+	}
+
+	// keeps any battle damage / buffs applied on top of the old base
+	pub fn set_base(&mut self, base: usize) {
+		self.value += base as isize - self.base as isize;
+		self.base = base;
+	}
 
 	pub fn reset(&mut self) {
 		self.value = self.base as isize;
@@ -376,17 +466,82 @@ impl Attribute {
 		self.value -= amount as isize;
 	}
 
-	// pub fn add(&mut self, amount: usize) {
-	//     self.value += amount as isize;
-	// }
+	pub fn add(&mut self, amount: usize) {
+		self.value += amount as isize;
+	}
+}
+
+#[derive(Component)]
+pub struct LevelCounter;
+
+#[derive(Component)]
+pub struct Level {
+	pub level: usize,
+	pub experience: usize,
+}
+
+impl Default for Level {
+	fn default() -> Self {
+		Self { level: 1, experience: 0 }
+	}
+}
+
+impl Level {
+	pub const MAX: usize = 3;
+
+	/// Experience required to reach the next level (level 1 -> 2 takes 2 merges, 2 -> 3 takes 3)
+	pub fn experience_to_next(&self) -> usize {
+		self.level + 1
+	}
+
+	pub fn is_max(&self) -> bool {
+		self.level >= Self::MAX
+	}
+
+	/// Returns how many levels were gained
+	pub fn add_experience(&mut self, amount: usize) -> usize {
+		let mut gained = 0;
+		self.experience += amount;
+		while !self.is_max() && self.experience >= self.experience_to_next() {
+			self.experience -= self.experience_to_next();
+			self.level += 1;
+			gained += 1;
+		}
+		if self.is_max() {
+			self.experience = 0;
+		}
+		gained
+	}
+}
+
+/// Buddies of the same species (face and color) can be merged together
+pub fn same_species(a: (&BuddyFace, &BuddyColor), b: (&BuddyFace, &BuddyColor)) -> bool {
+	a.0 == b.0 && a.1 == b.1
+}
+
+/// Merges another buddy's stats into this one. Both base stats become the best of the two plus
+/// one, and every level gained grants an extra point of each.
+pub fn merge_buddy(
+	health: &mut Health,
+	strength: &mut Strength,
+	level: &mut Level,
+	other_health: usize,
+	other_strength: usize,
+) {
+	let gained = level.add_experience(1);
+	let new_health = health.0.base().max(other_health) + 1 + gained;
+	let new_strength = strength.0.base().max(other_strength) + 1 + gained;
+	health.0.set_base(new_health);
+	strength.0.set_base(new_strength);
 }
 
 fn update_outlines(
 	time: Res<Time>,
+	settings: Res<Settings>,
 	mut outline_clock: ResMut<OutlineTimer>,
 	mut buddy_transforms: Query<&mut Transform, With<BuddyOutline>>,
 ) {
-	if !outline_clock.0.tick(time.delta()).just_finished() {
+	if !settings.wobble || !outline_clock.0.tick(time.delta()).just_finished() {
 		return;
 	}
 
@@ -449,8 +604,7 @@ fn set_buddy_face(
 
 #[derive(Component)]
 pub struct BuddyWobble {
-	animate_rotation: AnimateRange,
-	animate_translation: AnimateRange,
+	sway: Parallel<Transform>,
 	flipped: bool,
 }
 
@@ -465,39 +619,38 @@ impl BuddyWobble {
 	pub fn new(flipped: bool, percent: f32) -> Self {
 		let rot = PI * 0.05;
 		let trans = 10.0;
-		let rot_range;
-		let trans_range;
-		if flipped {
-			rot_range = rot..-rot;
-			trans_range = -trans..trans;
-		} else {
-			rot_range = -rot..rot;
-			trans_range = trans..-trans;
-		}
+		let (rot, trans) = if flipped { (rot, -trans) } else { (-rot, trans) };
 
 		let mut rng = rand::thread_rng();
 		let duration = Duration::from_secs_f32(rng.gen_range(2.0..5.0));
 		let ease = Ease::InOutCirc;
-		let mut animate_rotation = AnimateRange::new(duration, ease, rot_range, false);
-		let mut animate_translation = AnimateRange::new(duration, ease, trans_range, false);
-
-		animate_rotation.set_percent(percent);
-		animate_translation.set_percent(percent);
+		let mut sway = Parallel::new()
+			.with(Tween::new(
+				duration,
+				ease,
+				RotationLens {
+					start: Quat::from_rotation_z(rot),
+					end: Quat::from_rotation_z(-rot),
+				},
+			))
+			.with(Tween::new(
+				duration,
+				ease,
+				TranslationLens {
+					start: Vec3::new(trans, 0.0, 0.0),
+					end: Vec3::new(-trans, 0.0, 0.0),
+				},
+			));
+		sway.tick(duration.mul_f32(percent), &mut Transform::default());
 
-		Self { animate_rotation, animate_translation, flipped }
+		Self { sway, flipped }
 	}
 	pub fn wobble(&mut self, delta: Duration) -> Transform {
-		let z_rot = self.animate_rotation.tick(delta);
-		let x = self.animate_translation.tick(delta);
-		if self.animate_rotation.just_finished() {
+		let mut transform = Transform::default();
+		if self.sway.tick(delta, &mut transform).is_some() {
 			*self = BuddyWobble::new(!self.flipped, 0.0);
 		}
-
-		Transform {
-			translation: Vec3::new(x, 0.0, 0.0),
-			rotation: Quat::from_rotation_z(z_rot),
-			..Default::default()
-		}
+		transform
 	}
 }
 
@@ -505,13 +658,18 @@ fn move_buddy(
 	time: Res<Time>,
 	state: Res<State<AppState>>,
 	buddy_drag_state: Res<BuddyDragState>,
-	mut buddies: Query<(&mut Transform, &Side, &Slot, &Offset), With<Buddy>>,
+	mut buddies: Query<(&mut Transform, &Side, &Slot, &Offset, Option<&SwapHop>), With<Buddy>>,
 	pads: Query<(&Transform, &Side, &Slot), Without<Buddy>>,
 ) {
-	for (mut buddy_transform, buddy_side, buddy_slot, offset) in buddies.iter_mut() {
+	for (mut buddy_transform, buddy_side, buddy_slot, offset, hop) in buddies.iter_mut() {
 		for (pad_transform, pad_side, pad_slot) in pads.iter() {
 			if buddy_side == pad_side && buddy_slot.current == pad_slot.current {
-				if *state.current() == AppState::Shop {
+				let hop = hop.filter(|hop| *state.current() == AppState::Shop && !hop.finished());
+				if let Some(hop) = hop {
+					let mut transform = *pad_transform;
+					transform.translation = hop.position(pad_transform.translation);
+					*buddy_transform = transform * offset.0;
+				} else if *state.current() == AppState::Shop {
 					*buddy_transform =
 						lerp(*buddy_transform, *pad_transform, 5.0 * time.delta_seconds())
 							* offset.0;
@@ -522,8 +680,8 @@ fn move_buddy(
 		}
 	}
 
-	if let BuddyDragState::Dragging { buddy, offset } = &*buddy_drag_state {
-		if let Ok((mut transform, _, _, _)) = buddies.get_mut(*buddy) {
+	if let BuddyDragState::Dragging { buddy, offset, .. } = &*buddy_drag_state {
+		if let Ok((mut transform, ..)) = buddies.get_mut(*buddy) {
 			transform.translation.x = offset.x;
 			transform.translation.y = offset.y;
 		}
@@ -537,9 +695,18 @@ fn lerp(start: Transform, end: Transform, amount: f32) -> Transform {
 	transform
 }
 
-fn wobble_buddy(time: Res<Time>, mut buddies: Query<(&mut Transform, &mut BuddyWobble)>) {
+fn wobble_buddy(
+	time: Res<Time>,
+	settings: Res<Settings>,
+	mut buddies: Query<(&mut Transform, &mut BuddyWobble)>,
+) {
 	for (mut transform, mut wobble) in buddies.iter_mut() {
-		*transform = wobble.wobble(time.delta());
+		// the scale is left to the pop in when spawned
+		let scale = transform.scale;
+		// stand still rather than freeze halfway through a sway
+		*transform =
+			if settings.wobble { wobble.wobble(time.delta()) } else { Transform::default() };
+		transform.scale = scale;
 	}
 }
 
@@ -568,3 +735,16 @@ fn set_strength_counter(
 		}
 	}
 }
+
+fn set_level_counter(
+	parents: Query<&Parent>,
+	mut counters: Query<(&mut Text, &Parent), With<LevelCounter>>,
+	buddies: Query<&Level>,
+) {
+	for (mut text, parent) in counters.iter_mut() {
+		let buddy_entity = parents.get(parent.0).unwrap().0;
+		if let Ok(level) = buddies.get(buddy_entity) {
+			text.sections[0].value = level.level.to_string();
+		}
+	}
+}
@@ -0,0 +1,328 @@
+use crate::{
+	game::{
+		battle::BattleEvent,
+		buddy::{BuddyColor, Side},
+		replay::BattleOutcome,
+		ui::UiRoot,
+		Z_MESSAGE,
+	},
+	tween::{
+		Animator, Ease, Parallel, RotationLens, SpriteColorLens, TextColorLens, TranslationLens,
+		Tween,
+	},
+	widget::{spawn_game_button, ButtonPressed},
+	AppState,
+};
+use bevy::{input::mouse::MouseWheel, prelude::*};
+use std::{
+	f32::consts::{PI, TAU},
+	time::Duration,
+};
+
+pub struct CombatLogPlugin;
+
+impl Plugin for CombatLogPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<CombatLog>()
+			.add_system_set(SystemSet::on_enter(AppState::Battle).with_system(spawn_combat_log))
+			.add_system_set(SystemSet::on_enter(AppState::Replay).with_system(spawn_combat_log))
+			.add_system_set(add_combat_log_systems_to_set(SystemSet::on_update(AppState::Battle)))
+			.add_system_set(add_combat_log_systems_to_set(SystemSet::on_update(AppState::Replay)))
+			.add_system_set(SystemSet::on_exit(AppState::Battle).with_system(despawn_combat_log))
+			.add_system_set(SystemSet::on_exit(AppState::Replay).with_system(despawn_combat_log));
+	}
+}
+
+// live battles and replays both show the same effects
+fn add_combat_log_systems_to_set(set: SystemSet) -> SystemSet {
+	set.with_system(spawn_battle_effects)
+		.with_system(record_combat_log)
+		.with_system(combat_log_panel)
+}
+
+const VISIBLE_LINES: usize = 10;
+const FLOATING_TEXT_OFFSET: f32 = 90.0;
+const FAINT_PARTICLES: usize = 8;
+
+/// Every battle event of the current battle as a line of text
+#[derive(Default)]
+pub struct CombatLog {
+	pub entries: Vec<String>,
+	/// How many lines the panel is scrolled up from the latest entry
+	scroll: usize,
+	/// The panel is optional, this sticks between battles
+	pub visible: bool,
+}
+
+#[derive(Component)]
+pub struct CombatLogPanel;
+
+#[derive(Component)]
+pub struct CombatLogText;
+
+#[derive(Component)]
+pub struct CombatLogButton;
+
+fn spawn_combat_log(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	mut combat_log: ResMut<CombatLog>,
+	ui_root: Query<Entity, With<UiRoot>>,
+) {
+	combat_log.entries.clear();
+	combat_log.scroll = 0;
+
+	commands.entity(ui_root.single()).with_children(|parent| {
+		let style = Style {
+			size: Size::new(Val::Px(90.0), Val::Px(65.0)),
+			position_type: PositionType::Absolute,
+			position: Rect { right: Val::Px(100.0), top: Val::Px(40.0), ..Default::default() },
+			..Default::default()
+		};
+		spawn_game_button(parent, &asset_server, "Log", style, CombatLogButton);
+		parent
+			.spawn_bundle(NodeBundle {
+				style: Style {
+					size: Size::new(Val::Px(460.0), Val::Px(330.0)),
+					position_type: PositionType::Absolute,
+					position: Rect {
+						right: Val::Px(100.0),
+						top: Val::Px(115.0),
+						..Default::default()
+					},
+					padding: Rect::all(Val::Px(10.0)),
+					// text starts at the top of the panel
+					align_items: AlignItems::FlexEnd,
+					display: if combat_log.visible { Display::Flex } else { Display::None },
+					..Default::default()
+				},
+				color: Color::rgba(1.0, 1.0, 1.0, 0.8).into(),
+				..Default::default()
+			})
+			.insert(CombatLogPanel)
+			.with_children(|parent| {
+				parent
+					.spawn_bundle(TextBundle {
+						text: Text::with_section(
+							"",
+							TextStyle {
+								font: asset_server.load("font/CaveatBrush-Regular.ttf"),
+								font_size: 28.0,
+								color: Color::hex("323232").unwrap(),
+							},
+							Default::default(),
+						),
+						..Default::default()
+					})
+					.insert(CombatLogText);
+			});
+	});
+}
+
+fn despawn_combat_log(
+	mut commands: Commands,
+	entities: Query<Entity, Or<(With<CombatLogPanel>, With<CombatLogButton>)>>,
+) {
+	for entity in entities.iter() {
+		commands.entity(entity).despawn_recursive();
+	}
+}
+
+fn record_combat_log(
+	mut combat_log: ResMut<CombatLog>,
+	mut battle_events: EventReader<BattleEvent>,
+	buddies: Query<(&Side, &BuddyColor)>,
+) {
+	let name = |buddy: Entity| match buddies.get(buddy) {
+		Ok((Side::Left, color)) => format!("your {} buddy", color.name()),
+		Ok((_, color)) => format!("their {} buddy", color.name()),
+		Err(_) => "a buddy".to_string(),
+	};
+	for event in battle_events.iter() {
+		let entry = match event {
+			BattleEvent::Hit { attacker, target, blocked: true, .. } => {
+				format!("{}'s shield blocks {}", name(*target), name(*attacker))
+			},
+			BattleEvent::Hit { attacker, target, damage, .. } => {
+				format!("{} hits {} for {}", name(*attacker), name(*target), damage)
+			},
+			BattleEvent::Fainted { buddy } => format!("{} faints", name(*buddy)),
+			BattleEvent::Buffed { buddy, health, strength } => {
+				format!("{} gets +{}/+{}", name(*buddy), health, strength)
+			},
+			BattleEvent::Finished(BattleOutcome::Win) => "you win!".to_string(),
+			BattleEvent::Finished(BattleOutcome::Lose) => "you lose".to_string(),
+			BattleEvent::Finished(BattleOutcome::Tie) => "it's a tie".to_string(),
+		};
+		combat_log.entries.push(capitalize(&entry));
+	}
+}
+
+fn capitalize(text: &str) -> String {
+	let mut chars = text.chars();
+	match chars.next() {
+		Some(first) => first.to_uppercase().chain(chars).collect(),
+		None => String::new(),
+	}
+}
+
+fn combat_log_panel(
+	mut combat_log: ResMut<CombatLog>,
+	mut mouse_wheel: EventReader<MouseWheel>,
+	mut pressed: EventReader<ButtonPressed>,
+	log_buttons: Query<(), With<CombatLogButton>>,
+	mut panels: Query<&mut Style, With<CombatLogPanel>>,
+	mut texts: Query<&mut Text, With<CombatLogText>>,
+) {
+	for ButtonPressed(button) in pressed.iter() {
+		if log_buttons.get(*button).is_ok() {
+			combat_log.visible = !combat_log.visible;
+		}
+	}
+
+	if combat_log.visible {
+		let max_scroll = combat_log.entries.len().saturating_sub(VISIBLE_LINES);
+		for event in mouse_wheel.iter() {
+			// scrolling up shows older entries
+			let scroll = combat_log.scroll as f32 + event.y.signum();
+			combat_log.scroll = (scroll.max(0.0) as usize).min(max_scroll);
+		}
+	}
+
+	if !combat_log.is_changed() {
+		return;
+	}
+	for mut style in panels.iter_mut() {
+		style.display = if combat_log.visible { Display::Flex } else { Display::None };
+	}
+	let end = combat_log.entries.len() - combat_log.scroll.min(combat_log.entries.len());
+	let start = end.saturating_sub(VISIBLE_LINES);
+	for mut text in texts.iter_mut() {
+		text.sections[0].value = combat_log.entries[start..end].join("\n");
+	}
+}
+
+fn spawn_battle_effects(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	mut battle_events: EventReader<BattleEvent>,
+	buddies: Query<(&GlobalTransform, &BuddyColor)>,
+) {
+	for event in battle_events.iter() {
+		let (buddy, text, color) = match event {
+			BattleEvent::Hit { target, blocked: true, .. } => {
+				(*target, "Blocked".to_string(), Color::hex("646464").unwrap())
+			},
+			BattleEvent::Hit { target, damage, .. } => {
+				(*target, format!("-{}", damage), Color::hex("c83232").unwrap())
+			},
+			BattleEvent::Buffed { buddy, health, strength } => {
+				(*buddy, format!("+{}/+{}", health, strength), Color::hex("32a032").unwrap())
+			},
+			BattleEvent::Fainted { buddy } => {
+				if let Ok((transform, buddy_color)) = buddies.get(*buddy) {
+					spawn_faint_particles(
+						&mut commands,
+						&asset_server,
+						transform.translation,
+						buddy_color.color(),
+					);
+				}
+				continue;
+			},
+			BattleEvent::Finished(_) => continue,
+		};
+		if let Ok((transform, _)) = buddies.get(buddy) {
+			let start = Vec3::new(
+				transform.translation.x,
+				transform.translation.y + FLOATING_TEXT_OFFSET,
+				Z_MESSAGE,
+			);
+			spawn_floating_text(&mut commands, &asset_server, start, text, color);
+		}
+	}
+}
+
+fn spawn_floating_text(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	start: Vec3,
+	text: String,
+	color: Color,
+) {
+	commands
+		.spawn_bundle(Text2dBundle {
+			text: Text::with_section(
+				text,
+				TextStyle {
+					font: asset_server.load("font/CaveatBrush-Regular.ttf"),
+					font_size: 70.0,
+					color,
+				},
+				TextAlignment {
+					vertical: VerticalAlign::Center,
+					horizontal: HorizontalAlign::Center,
+				},
+			),
+			transform: Transform::from_translation(start),
+			..Default::default()
+		})
+		.insert(
+			Animator::new(Tween::new(
+				Duration::from_secs_f32(1.0),
+				Ease::OutBack,
+				TranslationLens { start, end: start + Vec3::new(0.0, 60.0, 0.0) },
+			))
+			.despawn_when_done(),
+		)
+		.insert(Animator::new(Tween::new(
+			Duration::from_secs_f32(1.0),
+			Ease::Linear,
+			TextColorLens { start: color, end: *color.clone().set_a(0.0) },
+		)));
+}
+
+fn spawn_faint_particles(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	at: Vec3,
+	color: Color,
+) {
+	let start = Vec3::new(at.x, at.y, Z_MESSAGE - 1.0);
+	for i in 0..FAINT_PARTICLES {
+		let angle = i as f32 / FAINT_PARTICLES as f32 * TAU;
+		let direction = Vec2::new(angle.cos(), angle.sin());
+		commands
+			.spawn_bundle(SpriteBundle {
+				texture: asset_server.load("buddy/base.png"),
+				sprite: Sprite { color, ..Default::default() },
+				transform: Transform::from_translation(start).with_scale(Vec3::splat(0.08)),
+				..Default::default()
+			})
+			.insert(
+				Animator::new(
+					Parallel::new()
+						.with(Tween::new(
+							Duration::from_secs_f32(0.6),
+							Ease::InOutCirc,
+							TranslationLens { start, end: start + (direction * 120.0).extend(0.0) },
+						))
+						// tumbling away from where the buddy fainted
+						.with(Tween::new(
+							Duration::from_secs_f32(0.6),
+							Ease::OutQuad,
+							RotationLens {
+								start: Quat::IDENTITY,
+								end: Quat::from_rotation_z(PI / 2.0),
+							},
+						)),
+				)
+				.despawn_when_done(),
+			)
+			.insert(Animator::new(Tween::new(
+				Duration::from_secs_f32(0.6),
+				Ease::InOutCirc,
+				SpriteColorLens { start: color, end: *color.clone().set_a(0.0) },
+			)));
+	}
+}
@@ -1,8 +1,15 @@
+use crate::game::balance::BalanceConfig;
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Default)]
 pub struct Coins(pub usize);
 
+impl FromWorld for Coins {
+	fn from_world(world: &mut World) -> Self {
+		Coins(world.get_resource_or_insert_with(BalanceConfig::default).run.starting_coins)
+	}
+}
+
 pub fn set_coin_text(coins: Res<Coins>, mut coin_texts: Query<&mut Text, With<CoinText>>) {
 	for mut text in coin_texts.iter_mut() {
 		text.sections[0].value = format!("{}", coins.0);
@@ -54,15 +61,42 @@ pub fn spawn_coins_element(commands: &mut ChildBuilder, asset_server: &AssetServ
 		});
 }
 
+/// Battles won and fought over the whole account, saved on the server
 #[derive(Default)]
 pub struct Trophies {
 	pub won: usize,
 	pub rounds: usize,
 }
 
-pub fn set_trophies_text(trophies: Res<Trophies>, mut texts: Query<&mut Text, With<TrophyText>>) {
+/// How the current run is going: trophies won, battles fought and defeats the player can still
+/// afford. `SavedRun` keeps it in `run.json` along with the team, so Continue picks the run up again.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct RunProgress {
+	pub won: usize,
+	pub rounds: usize,
+	pub lives: usize,
+	/// Coins paid in the shop, refunds from selling don't take any off. Opponents shop from
+	/// scratch every battle and get to spend as much.
+	#[serde(default)]
+	pub spent: usize,
+}
+
+impl FromWorld for RunProgress {
+	fn from_world(world: &mut World) -> Self {
+		let balance = world.get_resource_or_insert_with(BalanceConfig::default);
+		RunProgress::new(&balance)
+	}
+}
+
+impl RunProgress {
+	pub fn new(balance: &BalanceConfig) -> Self {
+		Self { won: 0, rounds: 0, lives: balance.run.lives, spent: 0 }
+	}
+}
+
+pub fn set_trophies_text(run: Res<RunProgress>, mut texts: Query<&mut Text, With<TrophyText>>) {
 	for mut text in texts.iter_mut() {
-		text.sections[0].value = format!("{}/{}", trophies.won, trophies.rounds);
+		text.sections[0].value = format!("{}/{}", run.won, run.rounds);
 	}
 }
 
@@ -110,3 +144,58 @@ pub fn spawn_trophies_element(commands: &mut ChildBuilder, asset_server: &AssetS
 				.insert(TrophyText);
 		});
 }
+
+pub fn set_lives_text(run: Res<RunProgress>, mut texts: Query<&mut Text, With<LivesText>>) {
+	for mut text in texts.iter_mut() {
+		text.sections[0].value = format!("{}", run.lives);
+	}
+}
+
+#[derive(Component)]
+pub struct LivesText;
+
+pub fn spawn_lives_element(commands: &mut ChildBuilder, asset_server: &AssetServer) {
+	commands
+		.spawn_bundle(NodeBundle {
+			style: Style {
+				size: Size::new(Val::Px(150.0), Val::Px(74.0)),
+				margin: Rect { top: Val::Px(4.0), left: Val::Px(4.0), ..Default::default() },
+				..Default::default()
+			},
+			color: Color::NONE.into(),
+			..Default::default()
+		})
+		.with_children(|parent| {
+			parent.spawn_bundle(ImageBundle {
+				style: Style {
+					size: Size::new(Val::Px(74.0), Val::Px(64.0)),
+					..Default::default()
+				},
+				image: UiImage(asset_server.load("buddy/health.png")),
+				..Default::default()
+			});
+			parent
+				.spawn_bundle(TextBundle {
+					style: Style {
+						size: Size::new(Val::Auto, Val::Px(32.0)),
+						margin: Rect { bottom: Val::Px(-13.0), ..Default::default() },
+
+						..Default::default()
+					},
+					text: Text::with_section(
+						"0",
+						TextStyle {
+							font: asset_server.load("font/CaveatBrush-Regular.ttf"),
+							font_size: 100.0,
+							color: Color::hex("323232").unwrap(),
+						},
+						TextAlignment {
+							vertical: VerticalAlign::Bottom,
+							horizontal: HorizontalAlign::Left,
+						},
+					),
+					..Default::default()
+				})
+				.insert(LivesText);
+		});
+}
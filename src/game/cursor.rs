@@ -0,0 +1,223 @@
+use crate::{
+	actions::{ActionPressed, GameAction, Pointer},
+	game::{
+		buddy::{Buddy, Side, Slot, TeamSize},
+		item::SHOP_ITEM_SLOTS,
+		pad::Pad,
+		sell::BuddySold,
+		shop::{BuddyDragState, Frozen, SHOP_BUDDY_SLOTS},
+		Z_PAD,
+	},
+	AppState,
+};
+use bevy::{ecs::schedule::SystemLabel, math::const_vec2, prelude::*};
+
+/// A cursor over the shop and team slots for playing with keys or a gamepad. It drives the same
+/// `Pointer` as the mouse, so picking up and dropping works exactly like dragging.
+pub struct ShopCursorPlugin;
+
+impl Plugin for ShopCursorPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<ShopCursor>()
+			.add_system_set(SystemSet::on_enter(AppState::Shop).with_system(spawn_shop_cursor))
+			.add_system_set(SystemSet::on_resume(AppState::Shop).with_system(resume_shop_cursor))
+			.add_system_set(
+				SystemSet::on_update(AppState::Shop)
+					.with_system(shop_cursor.label(MovePointer))
+					.with_system(position_shop_cursor.after(MovePointer)),
+			)
+			.add_system_set(SystemSet::on_pause(AppState::Shop).with_system(hide_shop_cursor))
+			.add_system_set(SystemSet::on_exit(AppState::Shop).with_system(despawn_shop_cursor));
+	}
+}
+
+/// Systems that read the `Pointer` run after this, so the cursor's presses land the same frame
+#[derive(SystemLabel, Clone, Hash, Debug, PartialEq, Eq)]
+pub struct MovePointer;
+
+// held things float a little above their slot
+const HOLD_LIFT: Vec2 = const_vec2!([0.0, 40.0]);
+const CURSOR_COLOR: Color = Color::rgba(1.0, 0.85, 0.2, 0.8);
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum CursorRow {
+	#[default]
+	Team,
+	Shop,
+}
+
+#[derive(Default)]
+pub struct ShopCursor {
+	row: CursorRow,
+	index: usize,
+	holding: bool,
+	/// Only shown once keys or a gamepad are used
+	shown: bool,
+	// set when the shop (re)opens, so a press that closed a menu isn't acted on again here
+	fresh: bool,
+}
+
+#[derive(Component)]
+pub struct CursorSprite;
+
+fn spawn_shop_cursor(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	mut cursor: ResMut<ShopCursor>,
+) {
+	cursor.holding = false;
+	cursor.fresh = true;
+	commands
+		.spawn_bundle(SpriteBundle {
+			texture: asset_server.load("pad.png"),
+			sprite: Sprite { color: CURSOR_COLOR, ..Default::default() },
+			visibility: Visibility { is_visible: false },
+			..Default::default()
+		})
+		.insert(CursorSprite);
+}
+
+fn resume_shop_cursor(mut cursor: ResMut<ShopCursor>) {
+	cursor.fresh = true;
+}
+
+fn row_len(row: CursorRow, team_size: &TeamSize) -> usize {
+	match row {
+		CursorRow::Team => team_size.0,
+		CursorRow::Shop => SHOP_BUDDY_SLOTS + SHOP_ITEM_SLOTS,
+	}
+}
+
+fn slot_position(
+	row: CursorRow,
+	index: usize,
+	pads: &Query<(&Transform, &Side, &Slot), With<Pad>>,
+) -> Option<Vec2> {
+	let side = match row {
+		CursorRow::Team => Side::Left,
+		CursorRow::Shop => Side::Shop,
+	};
+	pads.iter()
+		.find(|(_, pad_side, slot)| **pad_side == side && slot.current == index)
+		.map(|(transform, ..)| transform.translation.truncate())
+}
+
+fn shop_cursor(
+	mut commands: Commands,
+	mut cursor: ResMut<ShopCursor>,
+	mut pointer: ResMut<Pointer>,
+	mut buddy_drag_state: ResMut<BuddyDragState>,
+	mut actions: EventReader<ActionPressed>,
+	mut sold_events: EventWriter<BuddySold>,
+	mouse_button: Res<Input<MouseButton>>,
+	team_size: Res<TeamSize>,
+	pads: Query<(&Transform, &Side, &Slot), With<Pad>>,
+	buddies: Query<(Entity, &Side, &Slot, Option<&Frozen>), With<Buddy>>,
+) {
+	if cursor.fresh {
+		cursor.fresh = false;
+		actions.iter().for_each(drop);
+		return;
+	}
+	if mouse_button.just_pressed(MouseButton::Left) {
+		cursor.shown = false;
+		cursor.holding = false;
+	}
+	// picking up an empty slot doesn't start a drag
+	if cursor.holding && matches!(*buddy_drag_state, BuddyDragState::None) {
+		cursor.holding = false;
+		pointer.pressed = false;
+	}
+
+	let buddy_at = |side: Side, index: usize| {
+		buddies
+			.iter()
+			.find(|(_, buddy_side, slot, _)| **buddy_side == side && slot.current == index)
+	};
+
+	for ActionPressed(action) in actions.iter() {
+		cursor.shown = true;
+		match action {
+			GameAction::Up => cursor.row = CursorRow::Team,
+			GameAction::Down => cursor.row = CursorRow::Shop,
+			// both rows count their slots from the middle of the screen outwards, to the left
+			GameAction::Left => cursor.index += 1,
+			GameAction::Right => cursor.index = cursor.index.saturating_sub(1),
+			GameAction::Select => {
+				if let Some(position) = slot_position(cursor.row, cursor.index, &pads) {
+					if cursor.holding {
+						pointer.release(position);
+					} else {
+						pointer.press(position);
+					}
+					cursor.holding = !cursor.holding;
+				}
+			},
+			GameAction::Cancel => {
+				if cursor.holding {
+					*buddy_drag_state = BuddyDragState::None;
+					pointer.pressed = false;
+					cursor.holding = false;
+				}
+			},
+			GameAction::Sell => {
+				if !cursor.holding && cursor.row == CursorRow::Team {
+					if let Some((buddy, ..)) = buddy_at(Side::Left, cursor.index) {
+						sold_events.send(BuddySold { buddy });
+					}
+				}
+			},
+			GameAction::Freeze => {
+				if cursor.row == CursorRow::Shop {
+					if let Some((buddy, _, _, frozen)) = buddy_at(Side::Shop, cursor.index) {
+						if frozen.is_some() {
+							commands.entity(buddy).remove::<Frozen>();
+						} else {
+							commands.entity(buddy).insert(Frozen);
+						}
+					}
+				}
+			},
+			// the shop's buttons listen for these themselves
+			GameAction::Reroll | GameAction::Battle => {},
+		}
+		cursor.index = cursor.index.min(row_len(cursor.row, &team_size).saturating_sub(1));
+	}
+
+	if cursor.holding {
+		if let Some(position) = slot_position(cursor.row, cursor.index, &pads) {
+			pointer.position = Some(position + HOLD_LIFT);
+		}
+	}
+}
+
+fn position_shop_cursor(
+	cursor: Res<ShopCursor>,
+	team_size: Res<TeamSize>,
+	pads: Query<(&Transform, &Side, &Slot), With<Pad>>,
+	mut sprites: Query<(&mut Transform, &mut Visibility), (With<CursorSprite>, Without<Pad>)>,
+) {
+	let index = cursor.index.min(row_len(cursor.row, &team_size).saturating_sub(1));
+	let position = slot_position(cursor.row, index, &pads);
+	for (mut transform, mut visibility) in sprites.iter_mut() {
+		visibility.is_visible = cursor.shown && position.is_some();
+		if let Some(position) = position {
+			// under the buddy, around its pad
+			*transform = Transform::from_translation(position.extend(Z_PAD + 0.5))
+				.with_scale(Vec3::new(1.2, 1.6, 1.0));
+			transform.translation.y -= 60.0;
+		}
+	}
+}
+
+fn hide_shop_cursor(mut sprites: Query<&mut Visibility, With<CursorSprite>>) {
+	for mut visibility in sprites.iter_mut() {
+		visibility.is_visible = false;
+	}
+}
+
+fn despawn_shop_cursor(mut commands: Commands, sprites: Query<Entity, With<CursorSprite>>) {
+	for entity in sprites.iter() {
+		commands.entity(entity).despawn_recursive();
+	}
+}
@@ -0,0 +1,83 @@
+use crate::{
+	game::{balance::BalanceConfig, counters::RunProgress, rng::derive_seed},
+	network::Network,
+	types::{DailyScore, NetworkMessage},
+	AppState,
+};
+use bevy::prelude::*;
+
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<DailyChallenge>().add_system_set(
+			SystemSet::on_enter(AppState::RunSummary).with_system(post_daily_score),
+		);
+	}
+}
+
+// keeps daily seeds apart from the per-round streams derived from them
+const DAILY_STREAM: u64 = 4;
+
+/// The date of the daily challenge being played, if any. Everyone playing it gets the same seed,
+/// and its opponents don't adapt to the player so they face the same ones too.
+#[derive(Default)]
+pub struct DailyChallenge(pub Option<String>);
+
+pub struct Day {
+	/// Days since 1970-01-01 (UTC)
+	pub number: u64,
+	/// YYYY-MM-DD
+	pub date: String,
+}
+
+impl Day {
+	pub fn seed(&self) -> u64 {
+		derive_seed(self.number, DAILY_STREAM, 0)
+	}
+}
+
+/// Today in UTC, there's no clock to read on wasm
+pub fn today() -> Option<Day> {
+	if cfg!(target_arch = "wasm32") {
+		return None;
+	}
+	let since_epoch = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).ok()?;
+	let number = since_epoch.as_secs() / (60 * 60 * 24);
+	Some(Day { number, date: civil_date(number) })
+}
+
+// days since the epoch to a calendar date, see http://howardhinnant.github.io/date_algorithms.html
+fn civil_date(days: u64) -> String {
+	let z = days as i64 + 719_468;
+	let era = z.div_euclid(146_097);
+	let day_of_era = z.rem_euclid(146_097);
+	let year_of_era =
+		(day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let month_index = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+	let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+	let year = year_of_era + era * 400 + (month <= 2) as i64;
+	format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Daily opponents spend a fixed amount each round instead of matching the player's spending
+pub fn daily_budget(balance: &BalanceConfig, rounds_played: usize) -> usize {
+	balance.run.starting_coins + rounds_played * balance.rewards.tie
+}
+
+fn post_daily_score(
+	daily: Res<DailyChallenge>,
+	run: Res<RunProgress>,
+	mut network: ResMut<Network>,
+) {
+	if let Some(date) = &daily.0 {
+		network.send_message(&NetworkMessage::DailyScore(DailyScore {
+			date: date.clone(),
+			trophies: run.won as u32,
+			rounds: run.rounds as u32,
+			lives: run.lives as u32,
+		}));
+	}
+}
@@ -0,0 +1,418 @@
+use crate::{
+	actions::Pointer,
+	game::{
+		buddy::{same_species, Buddy, BuddyColor, BuddyFace, Level, Side, Slot, TeamSize},
+		counters::Coins,
+		pad::{Pad, PadSprite},
+		shop::{on_buddy, BuddyDragState, Price, PriceCounter, ShopEvent, Trash},
+		Z_BUDDY,
+	},
+	tween::{Animator, Delay, Ease, Sequence, TextColorLens, Tween, Tweenable, ValueLens},
+	AppState,
+};
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// Feedback while dragging in the shop: pads that would take the dragged buddy or item light up,
+/// a ghost shows where a buddy would land, swapped buddies hop over and buying without enough
+/// coins shakes the price.
+pub struct DragPlugin;
+
+impl Plugin for DragPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_system_set(SystemSet::on_enter(AppState::Shop).with_system(spawn_drop_ghost))
+			.add_system_set(
+				SystemSet::on_update(AppState::Shop)
+					.with_system(highlight_drop_targets)
+					.with_system(show_drop_ghost)
+					.with_system(start_shakes)
+					.with_system(shake_denied.after(start_shakes))
+					.with_system(tick_swap_hops),
+			)
+			.add_system_set(SystemSet::on_pause(AppState::Shop).with_system(clear_drag_feedback))
+			.add_system_set(SystemSet::on_exit(AppState::Shop).with_system(clear_drag_feedback))
+			.add_system_set(SystemSet::on_exit(AppState::Shop).with_system(despawn_drop_ghost));
+	}
+}
+
+const TARGET_COLOR: Color = Color::rgb(0.75, 1.0, 0.75);
+const HOVERED_TARGET_COLOR: Color = Color::rgb(0.4, 1.0, 0.4);
+const DENIED_COLOR: Color = Color::rgb(1.0, 0.4, 0.4);
+const GHOST_ALPHA: f32 = 0.35;
+const HOP_SECONDS: f32 = 0.4;
+const HOP_HEIGHT: f32 = 60.0;
+const SHAKE_SECONDS: f32 = 0.4;
+const SHAKE_DISTANCE: f32 = 10.0;
+const PRICE_COLOR: &str = "323232";
+
+/// Where letting go of a dragged buddy would put it
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DropTarget {
+	/// An empty team slot
+	Slot(usize),
+	/// Another team buddy, to swap places with or merge into
+	Buddy(Entity),
+	Trash,
+	None,
+}
+
+/// What's under `position` for `dragged` to be dropped on. Team buddies are found before the
+/// pads they stand on.
+pub fn drop_target<'a>(
+	position: Vec2,
+	reach: f32,
+	dragged: Entity,
+	team_size: usize,
+	buddies: impl Iterator<Item = (Entity, &'a Transform, &'a Side, &'a Slot)>,
+	pads: impl Iterator<Item = (&'a Transform, &'a Side, &'a Slot)>,
+	trash: Option<&Transform>,
+) -> DropTarget {
+	let team = buddies
+		.filter(|(entity, _, side, _)| *entity != dragged && **side == Side::Left)
+		.collect::<Vec<_>>();
+	if let Some((entity, ..)) =
+		team.iter().find(|(_, transform, ..)| on_buddy(position, transform, reach))
+	{
+		return DropTarget::Buddy(*entity);
+	}
+	let empty_pad = pads
+		.filter(|(_, side, slot)| **side == Side::Left && slot.current < team_size)
+		.filter(|(_, _, slot)| {
+			!team.iter().any(|(.., buddy_slot)| buddy_slot.current == slot.current)
+		})
+		.find(|(transform, ..)| on_buddy(position, transform, reach));
+	if let Some((_, _, slot)) = empty_pad {
+		return DropTarget::Slot(slot.current);
+	}
+	match trash {
+		Some(trash) if on_buddy(position, trash, reach) => DropTarget::Trash,
+		_ => DropTarget::None,
+	}
+}
+
+/// Moves a buddy that was swapped out of its slot in an arc, rather than sliding under the
+/// buddy taking its place
+#[derive(Component)]
+pub struct SwapHop {
+	pub from: Vec3,
+	// how far along the way to the new slot, and how high above it
+	slide: Tween<f32>,
+	lift: Sequence<f32>,
+	travelled: f32,
+	height: f32,
+	finished: bool,
+}
+
+impl SwapHop {
+	pub fn new(from: Vec3) -> Self {
+		let half = Duration::from_secs_f32(HOP_SECONDS / 2.0);
+		Self {
+			from,
+			slide: Tween::new(
+				Duration::from_secs_f32(HOP_SECONDS),
+				Ease::OutQuad,
+				ValueLens { start: 0.0, end: 1.0 },
+			),
+			lift: Sequence::new()
+				.then(Tween::new(half, Ease::OutSine, ValueLens { start: 0.0, end: HOP_HEIGHT }))
+				.then(Tween::new(half, Ease::InSine, ValueLens { start: HOP_HEIGHT, end: 0.0 })),
+			travelled: 0.0,
+			height: 0.0,
+			finished: false,
+		}
+	}
+
+	pub fn finished(&self) -> bool {
+		self.finished
+	}
+
+	/// Where the buddy is on its way from `from` to `to`
+	pub fn position(&self, to: Vec3) -> Vec3 {
+		self.from + (to - self.from) * self.travelled + Vec3::Y * self.height
+	}
+
+	fn tick(&mut self, delta: Duration) {
+		let slid = self.slide.tick(delta, &mut self.travelled).is_some();
+		let landed = self.lift.tick(delta, &mut self.height).is_some();
+		self.finished = slid && landed;
+	}
+}
+
+fn tick_swap_hops(
+	mut commands: Commands,
+	time: Res<Time>,
+	mut hops: Query<(Entity, &mut SwapHop)>,
+) {
+	for (entity, mut hop) in hops.iter_mut() {
+		hop.tick(time.delta());
+		if hop.finished() {
+			commands.entity(entity).remove::<SwapHop>();
+		}
+	}
+}
+
+/// Shakes something that couldn't be paid for and turns its price red for a moment
+#[derive(Component)]
+pub struct Shake {
+	swings: Sequence<f32>,
+	// what was added to the translation last frame, so the shake never drifts
+	applied: f32,
+}
+
+impl Shake {
+	/// Three swings to either side, each smaller than the last
+	fn new() -> Self {
+		let swings = 6;
+		let half_swing = Duration::from_secs_f32(SHAKE_SECONDS / (swings * 2) as f32);
+		let peak = |swing: usize| {
+			let side = (-1f32).powi(swing as i32);
+			let left = 1.0 - (swing * 2 + 1) as f32 / (swings * 2) as f32;
+			side * SHAKE_DISTANCE * left
+		};
+		let mut sequence = Sequence::new().then(Tween::new(
+			half_swing,
+			Ease::OutSine,
+			ValueLens { start: 0.0, end: peak(0) },
+		));
+		for swing in 1..swings {
+			sequence = sequence.then(Tween::new(
+				half_swing * 2,
+				Ease::InOutSine,
+				ValueLens { start: peak(swing - 1), end: peak(swing) },
+			));
+		}
+		let swings = sequence.then(Tween::new(
+			half_swing,
+			Ease::InSine,
+			ValueLens { start: peak(swings - 1), end: 0.0 },
+		));
+		Self { swings, applied: 0.0 }
+	}
+}
+
+fn start_shakes(
+	mut commands: Commands,
+	mut shop_events: EventReader<ShopEvent>,
+	children: Query<&Children>,
+	prices: Query<Entity, With<PriceCounter>>,
+) {
+	for event in shop_events.iter() {
+		let entity = match event {
+			ShopEvent::Denied(entity) => *entity,
+			_ => continue,
+		};
+		commands.entity(entity).insert(Shake::new());
+		let price_color = Color::hex(PRICE_COLOR).unwrap();
+		let children = children.get(entity).map(|children| children.iter()).into_iter().flatten();
+		for price in children.filter(|child| prices.get(**child).is_ok()) {
+			commands.entity(*price).insert(Animator::new(
+				Sequence::new()
+					.then(Tween::new(
+						Duration::from_millis(50),
+						Ease::OutQuad,
+						TextColorLens { start: price_color, end: DENIED_COLOR },
+					))
+					.then(Delay::new(Duration::from_millis(250)))
+					.then(Tween::new(
+						Duration::from_millis(200),
+						Ease::InQuad,
+						TextColorLens { start: DENIED_COLOR, end: price_color },
+					)),
+			));
+		}
+	}
+}
+
+fn shake_denied(
+	mut commands: Commands,
+	time: Res<Time>,
+	mut shaking: Query<(Entity, &mut Shake, &mut Transform)>,
+) {
+	for (entity, mut shake, mut transform) in shaking.iter_mut() {
+		let mut offset = shake.applied;
+		if shake.swings.tick(time.delta(), &mut offset).is_some() {
+			commands.entity(entity).remove::<Shake>();
+		}
+		transform.translation.x += offset - shake.applied;
+		shake.applied = offset;
+	}
+}
+
+fn highlight_drop_targets(
+	pointer: Res<Pointer>,
+	coins: Res<Coins>,
+	team_size: Res<TeamSize>,
+	buddy_drag_state: Res<BuddyDragState>,
+	buddies: Query<(Entity, &Transform, &Side, &Slot), With<Buddy>>,
+	species: Query<(&BuddyFace, &BuddyColor, &Level, Option<&Price>)>,
+	item_prices: Query<&Price, Without<Buddy>>,
+	pads: Query<(&Transform, &Side, &Slot), With<Pad>>,
+	trash: Query<&Transform, With<Trash>>,
+	mut pad_sprites: Query<(&Parent, &mut Sprite), With<PadSprite>>,
+) {
+	let position = pointer.position.unwrap_or_default();
+	let team = || buddies.iter().filter(|(_, _, side, _)| **side == Side::Left);
+	let buddy_in_slot =
+		|slot: usize| team().find(|(_, _, _, buddy_slot)| buddy_slot.current == slot);
+
+	// which team slots would take the dragged thing, which one it's over and if it's affordable
+	let mut targets = Vec::new();
+	let mut hovered = None;
+	let mut affordable = true;
+	match &*buddy_drag_state {
+		BuddyDragState::Dragging { buddy, .. } => {
+			let dragged = *buddy;
+			let dragged_species = species.get(dragged).ok();
+			let from_shop = matches!(buddies.get(dragged), Ok((_, _, Side::Shop, _)));
+			for slot in 0..team_size.0 {
+				let valid = match (buddy_in_slot(slot), dragged_species) {
+					(None, _) => true,
+					// shop buddies merge into their own species, team buddies swap with anyone
+					(Some((target, ..)), Some((face, color, ..))) if from_shop => {
+						species.get(target).is_ok_and(|(target_face, target_color, level, _)| {
+							same_species((face, color), (target_face, target_color))
+								&& !level.is_max()
+						})
+					},
+					(Some((target, ..)), _) => target != dragged,
+				};
+				if valid {
+					targets.push(slot);
+				}
+			}
+			hovered = match drop_target(
+				position,
+				pointer.reach,
+				dragged,
+				team_size.0,
+				buddies.iter(),
+				pads.iter(),
+				trash.get_single().ok(),
+			) {
+				DropTarget::Slot(slot) => Some(slot),
+				DropTarget::Buddy(target) => {
+					buddies.get(target).ok().map(|(.., slot)| slot.current)
+				},
+				DropTarget::Trash | DropTarget::None => None,
+			};
+			if from_shop {
+				let price =
+					dragged_species.and_then(|(.., price)| price).map_or(0, |price| price.0);
+				affordable = coins.0 >= price;
+			}
+		},
+		BuddyDragState::DraggingItem { item, .. } => {
+			// items are used on team buddies
+			for (_, transform, _, slot) in team() {
+				targets.push(slot.current);
+				if on_buddy(position, transform, pointer.reach) {
+					hovered = Some(slot.current);
+				}
+			}
+			affordable = item_prices.get(*item).map_or(true, |price| coins.0 >= price.0);
+		},
+		BuddyDragState::None => {},
+	}
+
+	for (parent, mut sprite) in pad_sprites.iter_mut() {
+		let slot = match pads.get(parent.0) {
+			Ok((_, Side::Left, slot)) => slot.current,
+			_ => continue,
+		};
+		sprite.color = if !targets.contains(&slot) {
+			Color::WHITE
+		} else if hovered == Some(slot) {
+			if affordable {
+				HOVERED_TARGET_COLOR
+			} else {
+				DENIED_COLOR
+			}
+		} else {
+			TARGET_COLOR
+		};
+	}
+}
+
+#[derive(Component)]
+pub struct DropGhost;
+
+fn spawn_drop_ghost(mut commands: Commands, asset_server: Res<AssetServer>) {
+	commands
+		.spawn_bundle(SpriteBundle {
+			texture: asset_server.load("buddy/base.png"),
+			transform: Transform::from_xyz(0.0, 0.0, Z_BUDDY - 0.5).with_scale(Vec3::splat(0.5)),
+			visibility: Visibility { is_visible: false },
+			..Default::default()
+		})
+		.insert(DropGhost);
+}
+
+// a see-through copy of the dragged buddy on the empty slot or team buddy it would land on
+fn show_drop_ghost(
+	pointer: Res<Pointer>,
+	team_size: Res<TeamSize>,
+	buddy_drag_state: Res<BuddyDragState>,
+	buddies: Query<(Entity, &Transform, &Side, &Slot), With<Buddy>>,
+	colors: Query<&BuddyColor>,
+	pads: Query<(&Transform, &Side, &Slot), With<Pad>>,
+	mut ghosts: Query<
+		(&mut Transform, &mut Sprite, &mut Visibility),
+		(With<DropGhost>, Without<Buddy>, Without<Pad>),
+	>,
+) {
+	let mut landing = None;
+	if let (BuddyDragState::Dragging { buddy, .. }, Some(position)) =
+		(&*buddy_drag_state, pointer.position)
+	{
+		let from_shop = matches!(buddies.get(*buddy), Ok((_, _, Side::Shop, _)));
+		let slot = match drop_target(
+			position,
+			pointer.reach,
+			*buddy,
+			team_size.0,
+			buddies.iter(),
+			pads.iter(),
+			None,
+		) {
+			DropTarget::Slot(slot) => Some(slot),
+			// shop buddies merge rather than land, the highlighted pad says enough
+			DropTarget::Buddy(target) if !from_shop => {
+				buddies.get(target).ok().map(|(.., slot)| slot.current)
+			},
+			_ => None,
+		};
+		let pad = slot.and_then(|slot| {
+			pads.iter()
+				.find(|(_, side, pad_slot)| **side == Side::Left && pad_slot.current == slot)
+		});
+		if let (Some((pad_transform, ..)), Ok(color)) = (pad, colors.get(*buddy)) {
+			landing = Some((pad_transform.translation, color.color()));
+		}
+	}
+
+	for (mut transform, mut sprite, mut visibility) in ghosts.iter_mut() {
+		visibility.is_visible = landing.is_some();
+		if let Some((position, color)) = landing {
+			transform.translation = position.truncate().extend(Z_BUDDY - 0.5);
+			sprite.color = *color.clone().set_a(GHOST_ALPHA);
+		}
+	}
+}
+
+fn clear_drag_feedback(
+	mut pad_sprites: Query<&mut Sprite, With<PadSprite>>,
+	mut ghosts: Query<&mut Visibility, With<DropGhost>>,
+) {
+	for mut sprite in pad_sprites.iter_mut() {
+		sprite.color = Color::WHITE;
+	}
+	for mut visibility in ghosts.iter_mut() {
+		visibility.is_visible = false;
+	}
+}
+
+fn despawn_drop_ghost(mut commands: Commands, ghosts: Query<Entity, With<DropGhost>>) {
+	for entity in ghosts.iter() {
+		commands.entity(entity).despawn_recursive();
+	}
+}
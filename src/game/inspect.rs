@@ -0,0 +1,192 @@
+use crate::{
+	actions::Pointer,
+	connect::{Collection, Tokens},
+	game::{
+		balance::BalanceConfig,
+		buddy::{Attribute, Buddy, BuddyColor, BuddyFace, Health, Level, Side, Strength},
+		cursor::MovePointer,
+		item::{FaintBuff, Shielded},
+		sell::sell_value,
+		shop::{on_buddy, BuddyDragState, Price},
+	},
+	tooltip::{spawn_tooltip, Tooltip},
+	AppState,
+};
+use bevy::{math::const_vec2, prelude::*};
+
+/// Hovering a buddy, or holding a finger still on it, shows its details in a tooltip
+pub struct InspectPlugin;
+
+impl Plugin for InspectPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<Inspected>()
+			.add_system_set(
+				SystemSet::on_update(AppState::Shop)
+					.with_system(inspect_buddy.after(MovePointer))
+					.with_system(show_inspected.after(inspect_buddy)),
+			)
+			.add_system_set(SystemSet::on_pause(AppState::Shop).with_system(stop_inspecting))
+			.add_system_set(SystemSet::on_exit(AppState::Shop).with_system(stop_inspecting));
+	}
+}
+
+const TOOLTIP_OFFSET: Vec2 = const_vec2!([0.0, 110.0]);
+
+/// The buddy being looked at more closely
+#[derive(Default)]
+pub struct Inspected(pub Option<Entity>);
+
+#[derive(Component)]
+pub struct InspectTooltip;
+
+fn inspect_buddy(
+	pointer: Res<Pointer>,
+	mut inspected: ResMut<Inspected>,
+	mut buddy_drag_state: ResMut<BuddyDragState>,
+	buddies: Query<(Entity, &Transform), With<Buddy>>,
+) {
+	let buddy_under = |position: Option<Vec2>| {
+		let position = position?;
+		buddies
+			.iter()
+			.find(|(_, transform)| on_buddy(position, transform, pointer.reach))
+			.map(|(entity, _)| entity)
+	};
+
+	if pointer.just_long_pressed {
+		inspected.0 = buddy_under(pointer.position);
+		// whatever the press picked up goes back, lifting the finger shouldn't buy or move it
+		*buddy_drag_state = BuddyDragState::None;
+	} else if pointer.inspecting {
+		// the long pressed buddy stays inspected until the finger lifts
+	} else if pointer.hovering && matches!(*buddy_drag_state, BuddyDragState::None) {
+		inspected.0 = buddy_under(pointer.position);
+	} else {
+		inspected.0 = None;
+	}
+}
+
+fn show_inspected(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	inspected: Res<Inspected>,
+	balance: Res<BalanceConfig>,
+	collection: Res<Collection>,
+	tokens: Res<Tokens>,
+	buddies: Query<
+		(
+			&Side,
+			&BuddyFace,
+			&BuddyColor,
+			&Level,
+			&Health,
+			&Strength,
+			Option<&Price>,
+			Option<&Shielded>,
+			Option<&FaintBuff>,
+		),
+		With<Buddy>,
+	>,
+	tooltips: Query<(Entity, &Tooltip), With<InspectTooltip>>,
+	mut texts: Query<&mut Text>,
+) {
+	let mut shown = false;
+	for (entity, tooltip) in tooltips.iter() {
+		if Some(tooltip.anchor) == inspected.0 {
+			shown = true;
+		} else {
+			commands.entity(entity).despawn_recursive();
+		}
+	}
+	let buddy = match inspected.0 {
+		Some(buddy) => buddy,
+		None => return,
+	};
+	let (side, face, color, level, health, strength, price, shielded, faint_buff) =
+		match buddies.get(buddy) {
+			Ok(details) => details,
+			Err(_) => return,
+		};
+
+	let mut lines = vec![format!("{} {} buddy", color.name(), face.name())];
+	lines.push(if level.is_max() {
+		format!("Level {} (max)", level.level)
+	} else {
+		format!(
+			"Level {} ({}/{} to next)",
+			level.level,
+			level.experience,
+			level.experience_to_next()
+		)
+	});
+	lines.push(attribute_line("Health", &health.0));
+	lines.push(attribute_line("Strength", &strength.0));
+	if shielded.is_some() {
+		lines.push("Shield: blocks the next hit".to_string());
+	}
+	if let Some(buff) = faint_buff {
+		lines.push(format!(
+			"Honey: +{}/+{} to the next buddy when it faints",
+			buff.health, buff.strength
+		));
+	}
+	match (side, price) {
+		(Side::Shop, Some(price)) => lines.push(format!("Costs {}", price.0)),
+		(Side::Left, _) => {
+			lines.push(format!("Sells for {}", sell_value(&balance, level, health, strength)))
+		},
+		_ => {},
+	}
+	if let Some(token_id) = owned_token(&collection, &tokens, face, color) {
+		lines.push(format!("Token #{}", token_id));
+	}
+	let text = lines.join("\n");
+
+	if shown {
+		for (_, tooltip) in tooltips.iter() {
+			tooltip.set_text(&mut texts, text.clone());
+		}
+	} else {
+		let tooltip = spawn_tooltip(&mut commands, &asset_server, buddy, TOOLTIP_OFFSET, text);
+		commands.entity(tooltip).insert(InspectTooltip);
+	}
+}
+
+fn attribute_line(name: &str, attribute: &Attribute) -> String {
+	if attribute.value() == attribute.base() {
+		format!("{}: {}", name, attribute.base())
+	} else {
+		format!("{}: {} ({} base)", name, attribute.value(), attribute.base())
+	}
+}
+
+// owned buddies come from the account's collection, one of the same species counts
+fn owned_token<'a>(
+	collection: &Collection,
+	tokens: &'a Tokens,
+	face: &BuddyFace,
+	color: &BuddyColor,
+) -> Option<&'a str> {
+	let color = color.color().as_rgba_f32();
+	let to_u8 = |channel: f32| (channel * 255.0).round() as u8;
+	collection
+		.0
+		.iter()
+		.position(|character| {
+			BuddyFace::from_index(character.face) == *face
+				&& (character.color.r, character.color.g, character.color.b)
+					== (to_u8(color[0]), to_u8(color[1]), to_u8(color[2]))
+		})
+		.and_then(|index| tokens.get(index))
+}
+
+fn stop_inspecting(
+	mut commands: Commands,
+	mut inspected: ResMut<Inspected>,
+	tooltips: Query<Entity, With<InspectTooltip>>,
+) {
+	inspected.0 = None;
+	for entity in tooltips.iter() {
+		commands.entity(entity).despawn_recursive();
+	}
+}
@@ -0,0 +1,322 @@
+use crate::{
+	actions::Pointer,
+	game::{
+		balance::BalanceConfig,
+		buddy::{Buddy, Health, Side, Slot, Strength},
+		counters::{Coins, RunProgress},
+		cursor::MovePointer,
+		pad::{spawn_pad, Pad},
+		pause::unpaused,
+		rng::GameRng,
+		shop::{
+			add_price, on_buddy, BuddyDragState, Price, ShopEvent, MAX_REACH, SHOP_BUDDY_SLOTS,
+		},
+		Z_BUDDY,
+	},
+	AppState,
+};
+use bevy::{
+	math::{const_vec2, Vec3Swizzles},
+	prelude::*,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub struct ItemPlugin;
+
+impl Plugin for ItemPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_system(move_item.with_run_criteria(unpaused))
+			.add_system_set(SystemSet::on_enter(AppState::Startup).with_system(spawn_item_pads))
+			.add_system_set(SystemSet::on_enter(AppState::Shop).with_system(enter_shop_items))
+			.add_system_set(
+				SystemSet::on_update(AppState::Shop).with_system(drag_item.after(MovePointer)),
+			);
+	}
+}
+
+// items sit on the shop pads right after the buddy offers
+pub const SHOP_ITEM_SLOTS: usize = 2;
+const ITEM_EXTENTS: Vec2 = const_vec2!([35.0, 35.0]);
+const ITEM_PRICE_POSITION: Vec2 = const_vec2!([-45.0, 40.0]);
+const SHIELD_ICON_POSITION: Vec2 = const_vec2!([-75.0, 50.0]);
+const FAINT_BUFF_ICON_POSITION: Vec2 = const_vec2!([-75.0, 5.0]);
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum Item {
+	/// +1 Health, permanently
+	Apple,
+	/// +1 Strength, permanently
+	Meat,
+	/// +3 Strength until the end of the next battle
+	Pepper,
+	/// Blocks the next hit
+	Shield,
+	/// When this buddy faints, the buddy behind it gets +2/+2 for the rest of the battle
+	Honey,
+}
+
+impl Item {
+	const ITEMS: &'static [Item] =
+		&[Item::Apple, Item::Meat, Item::Pepper, Item::Shield, Item::Honey];
+
+	pub fn get_path(&self) -> &'static str {
+		match self {
+			Item::Apple => "item/apple.png",
+			Item::Meat => "item/meat.png",
+			Item::Pepper => "item/pepper.png",
+			Item::Shield => "item/shield.png",
+			Item::Honey => "item/honey.png",
+		}
+	}
+
+	pub fn random(rng: &mut impl Rng) -> Item {
+		Self::ITEMS[rng.gen_range(0..Self::ITEMS.len())]
+	}
+}
+
+#[derive(Component)]
+pub struct ShopItem {
+	pub slot: usize,
+}
+
+/// Absorbs the next hit, then the icon is removed along with this component
+#[derive(Component)]
+pub struct Shielded {
+	icon: Entity,
+}
+
+#[derive(Component, Clone, Copy, Serialize, Deserialize)]
+pub struct FaintBuff {
+	pub health: usize,
+	pub strength: usize,
+}
+
+pub fn spawn_item_pads(mut commands: Commands, asset_server: Res<AssetServer>) {
+	for i in 0..SHOP_ITEM_SLOTS {
+		spawn_pad(&mut commands, &asset_server, Side::Shop, Slot::new(SHOP_BUDDY_SLOTS + i));
+	}
+}
+
+pub fn enter_shop_items(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	balance: Res<BalanceConfig>,
+	run: Res<RunProgress>,
+	mut game_rng: ResMut<GameRng>,
+	items: Query<Entity, With<ShopItem>>,
+) {
+	// clean up old shop items
+	for entity in items.iter() {
+		commands.entity(entity).despawn_recursive();
+	}
+
+	spawn_shop_items(&mut commands, &asset_server, &balance, game_rng.items(run.rounds));
+}
+
+pub fn spawn_shop_items(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	balance: &BalanceConfig,
+	rng: &mut impl Rng,
+) {
+	for i in 0..SHOP_ITEM_SLOTS {
+		let item = Item::random(rng);
+		let item_id = commands
+			.spawn_bundle((
+				item,
+				ShopItem { slot: SHOP_BUDDY_SLOTS + i },
+				Transform::from_xyz(0.0, -500.0, 0.0),
+				GlobalTransform::default(),
+			))
+			.with_children(|parent| {
+				parent.spawn_bundle(SpriteBundle {
+					texture: asset_server.load(item.get_path()),
+					transform: Transform::from_xyz(0.0, 0.0, Z_BUDDY).with_scale(Vec3::splat(0.5)),
+					..Default::default()
+				});
+			})
+			.id();
+		add_price(commands, asset_server, item_id, balance.item_price, ITEM_PRICE_POSITION);
+	}
+}
+
+fn move_item(
+	time: Res<Time>,
+	buddy_drag_state: Res<BuddyDragState>,
+	mut items: Query<(Entity, &mut Transform, &ShopItem)>,
+	pads: Query<(&Transform, &Side, &Slot), (With<Pad>, Without<ShopItem>)>,
+) {
+	for (entity, mut item_transform, item) in items.iter_mut() {
+		if let BuddyDragState::DraggingItem { item, offset } = &*buddy_drag_state {
+			if *item == entity {
+				item_transform.translation.x = offset.x;
+				item_transform.translation.y = offset.y;
+				continue;
+			}
+		}
+
+		for (pad_transform, pad_side, pad_slot) in pads.iter() {
+			if *pad_side == Side::Shop && pad_slot.current == item.slot {
+				let delta = pad_transform.translation - item_transform.translation;
+				item_transform.translation += delta * (5.0 * time.delta_seconds()).min(1.0);
+			}
+		}
+	}
+}
+
+fn drag_item(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	mut coins: ResMut<Coins>,
+	mut run: ResMut<RunProgress>,
+	mut buddy_drag_state: ResMut<BuddyDragState>,
+	mut shop_events: EventWriter<ShopEvent>,
+	pointer: Res<Pointer>,
+	items: Query<(Entity, &Transform, &Item, &Price), With<ShopItem>>,
+	mut buddies: Query<
+		(
+			Entity,
+			&Transform,
+			&Side,
+			&mut Health,
+			&mut Strength,
+			Option<&Shielded>,
+			Option<&FaintBuff>,
+		),
+		With<Buddy>,
+	>,
+) {
+	let cursor_world = if let Some(cursor) = pointer.position {
+		cursor
+	} else {
+		return;
+	};
+
+	if let BuddyDragState::DraggingItem { offset, .. } = &mut *buddy_drag_state {
+		*offset = cursor_world;
+	}
+	if pointer.just_pressed {
+		if let BuddyDragState::None = &*buddy_drag_state {
+			for (entity, transform, _, _) in items.iter() {
+				if on_item(cursor_world, transform, pointer.reach) {
+					*buddy_drag_state =
+						BuddyDragState::DraggingItem { item: entity, offset: cursor_world };
+					break;
+				}
+			}
+		}
+	}
+	if pointer.just_released {
+		if let BuddyDragState::DraggingItem { item: item_entity, .. } = &*buddy_drag_state {
+			let (_, _, item, price) = items.get(*item_entity).unwrap();
+			let target = buddies
+				.iter()
+				.find(|(_, transform, side, ..)| {
+					**side == Side::Left && on_buddy(cursor_world, transform, pointer.reach)
+				})
+				.map(|(buddy, ..)| buddy);
+			if target.is_some() && coins.0 < price.0 {
+				shop_events.send(ShopEvent::Denied(*item_entity));
+			} else if let Some(buddy) = target {
+				let (_, _, _, mut health, mut strength, shielded, faint_buff) =
+					buddies.get_mut(buddy).unwrap();
+				let used = match item {
+					Item::Apple => {
+						let base = health.0.base();
+						health.0.set_base(base + 1);
+						true
+					},
+					Item::Meat => {
+						let base = strength.0.base();
+						strength.0.set_base(base + 1);
+						true
+					},
+					// only lasts until Attribute::reset at the end of the next battle
+					Item::Pepper => {
+						strength.0.add(3);
+						true
+					},
+					Item::Shield if shielded.is_none() => {
+						add_shield(&mut commands, &asset_server, buddy);
+						true
+					},
+					Item::Honey if faint_buff.is_none() => {
+						add_faint_buff(
+							&mut commands,
+							&asset_server,
+							buddy,
+							FaintBuff { health: 2, strength: 2 },
+						);
+						true
+					},
+					_ => false,
+				};
+				if used {
+					coins.0 -= price.0;
+					run.spent += price.0;
+					commands.entity(*item_entity).despawn_recursive();
+					shop_events.send(ShopEvent::Bought);
+				}
+			}
+			*buddy_drag_state = BuddyDragState::None;
+		}
+	}
+}
+
+pub fn add_shield(commands: &mut Commands, asset_server: &AssetServer, buddy: Entity) {
+	let icon = spawn_item_icon(commands, asset_server, buddy, Item::Shield, SHIELD_ICON_POSITION);
+	commands.entity(buddy).insert(Shielded { icon });
+}
+
+pub fn add_faint_buff(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	buddy: Entity,
+	faint_buff: FaintBuff,
+) {
+	spawn_item_icon(commands, asset_server, buddy, Item::Honey, FAINT_BUFF_ICON_POSITION);
+	commands.entity(buddy).insert(faint_buff);
+}
+
+fn spawn_item_icon(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	buddy: Entity,
+	item: Item,
+	position: Vec2,
+) -> Entity {
+	let mut icon = None;
+	commands.entity(buddy).with_children(|parent| {
+		icon = Some(
+			parent
+				.spawn_bundle(SpriteBundle {
+					texture: asset_server.load(item.get_path()),
+					transform: Transform::from_translation(position.extend(Z_BUDDY + 0.3))
+						.with_scale(Vec3::splat(0.3)),
+					..Default::default()
+				})
+				.id(),
+		);
+	});
+	icon.unwrap()
+}
+
+/// Returns true if the hit was absorbed by a shield, which is used up
+pub fn block_hit(commands: &mut Commands, shielded: &Query<&Shielded>, buddy: Entity) -> bool {
+	if let Ok(shielded) = shielded.get(buddy) {
+		commands.entity(shielded.icon).despawn_recursive();
+		commands.entity(buddy).remove::<Shielded>();
+		true
+	} else {
+		false
+	}
+}
+
+fn on_item(position: Vec2, item_transform: &Transform, reach: f32) -> bool {
+	let pos = item_transform.translation;
+	let extents = ITEM_EXTENTS + Vec2::splat(reach.min(MAX_REACH));
+	let min = pos.xy() - extents;
+	let max = pos.xy() + extents;
+	position.x < max.x && position.x > min.x && position.y < max.y && position.y > min.y
+}
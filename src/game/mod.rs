@@ -1,15 +1,45 @@
-pub mod animate;
+pub mod balance;
 pub mod battle;
 pub mod buddy;
+pub mod combat_log;
 pub mod counters;
+pub mod cursor;
+pub mod daily;
+pub mod drag;
+pub mod inspect;
+pub mod item;
+pub mod opponent;
 pub mod pad;
+pub mod pause;
+pub mod replay;
+pub mod rng;
+pub mod run;
+pub mod sell;
 pub mod shop;
+pub mod simulate;
+pub mod skin;
 pub mod ui;
 
 use crate::{
 	game::{
-		animate::AnimatePlugin, battle::BattlePlugin, buddy::BuddyPlugin, counters::Coins,
-		pad::spawn_pads, shop::ShopPlugin,
+		balance::BalanceConfig,
+		battle::BattlePlugin,
+		buddy::{BuddyPlugin, Side, TeamSize},
+		combat_log::CombatLogPlugin,
+		counters::Coins,
+		cursor::ShopCursorPlugin,
+		daily::DailyPlugin,
+		drag::DragPlugin,
+		inspect::InspectPlugin,
+		item::ItemPlugin,
+		pad::frame_camera,
+		pause::{PausePlugin, SavedTeam},
+		replay::{spawn_recorded_buddy, ReplayPlugin},
+		rng::GameRng,
+		run::RunPlugin,
+		sell::SellPlugin,
+		shop::ShopPlugin,
+		skin::SkinPlugin,
 	},
 	AppState,
 };
@@ -25,35 +55,65 @@ pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
 	fn build(&self, app: &mut App) {
-		app.insert_resource(Coins(20))
+		app.init_resource::<BalanceConfig>()
+			.init_resource::<Coins>()
+			.init_resource::<TeamSize>()
+			.init_resource::<GameRng>()
+			.add_system(frame_camera)
 			.add_plugin(BuddyPlugin)
-			.add_plugin(AnimatePlugin)
 			.add_plugin(ShopPlugin)
+			.add_plugin(ItemPlugin)
+			.add_plugin(SellPlugin)
 			.add_plugin(BattlePlugin)
+			.add_plugin(CombatLogPlugin)
+			.add_plugin(RunPlugin)
+			.add_plugin(DailyPlugin)
+			.add_plugin(ReplayPlugin)
+			.add_plugin(PausePlugin)
+			.add_plugin(ShopCursorPlugin)
+			.add_plugin(InspectPlugin)
+			.add_plugin(DragPlugin)
+			.add_plugin(SkinPlugin)
 			.add_system_set(SystemSet::on_enter(AppState::Startup).with_system(setup_game));
 	}
 }
 
+#[derive(Component)]
+pub struct Foreground;
+
 pub struct BattleMessages {
 	you_win: Entity,
 	you_lose: Entity,
 	you_tie: Entity,
 }
 
+impl BattleMessages {
+	pub fn entities(&self) -> [Entity; 3] {
+		[self.you_win, self.you_lose, self.you_tie]
+	}
+}
+
 pub fn setup_game(
 	mut commands: Commands,
 	asset_server: Res<AssetServer>,
 	mut state: ResMut<State<AppState>>,
+	mut saved_team: ResMut<SavedTeam>,
+	mut team_size: ResMut<TeamSize>,
 ) {
 	spawn_ui(&mut commands, &asset_server);
+	// coming back from the menu, the team was kept aside and its pads need spawning again
+	for record in saved_team.0.drain(..) {
+		spawn_recorded_buddy(&mut commands, &asset_server, &record, Side::Left);
+	}
+	team_size.set_changed();
 
-	commands.spawn_bundle(SpriteBundle {
-		texture: asset_server.load("foreground.png"),
-		transform: Transform::from_xyz(0.0, 100.0, Z_FOREGROUND),
-		..Default::default()
-	});
-
-	spawn_pads(&mut commands, &asset_server);
+	commands
+		.spawn_bundle(SpriteBundle {
+			texture: asset_server.load("foreground.png"),
+			transform: Transform::from_xyz(0.0, 100.0, Z_FOREGROUND),
+			..Default::default()
+		})
+		.insert(Foreground);
 
 	let you_win = commands
 		.spawn_bundle(SpriteBundle {
@@ -0,0 +1,317 @@
+use crate::game::{
+	balance::BalanceConfig,
+	battle::fight,
+	buddy::{
+		merge_buddy, same_species, Attribute, BuddyColor, BuddyFace, BuddyTemplate, Health, Level,
+		Strength,
+	},
+	counters::Coins,
+	item::{FaintBuff, Item},
+	replay::{BattleOutcome, BuddyRecord},
+	sell::sell_value,
+	shop::SHOP_BUDDY_SLOTS,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// matches the shop, which puts two items next to the buddy offers
+const SHOP_ITEMS: usize = 2;
+// rerolls allowed per shop visit before settling for what's there
+const MAX_REROLLS: usize = 3;
+// an offer has to be this much stronger before the weakest buddy gets sold for it
+const UPGRADE_MARGIN: usize = 2;
+// buddies an opponent always fields (team size permitting), however little the player spent
+const MIN_TEAM_SIZE: usize = 2;
+
+/// How cleverly opponents spend their coins
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Difficulty {
+	/// Buys whatever has the most stats
+	Easy,
+	/// Also merges matching buddies, uses items and lines the team up around them
+	#[default]
+	Normal,
+	/// Also tries every lineup against the player's team and picks the one that does best
+	Hard,
+}
+
+impl Difficulty {
+	pub fn name(&self) -> &'static str {
+		match self {
+			Difficulty::Easy => "Easy",
+			Difficulty::Normal => "Normal",
+			Difficulty::Hard => "Hard",
+		}
+	}
+
+	pub fn next(&self) -> Difficulty {
+		match self {
+			Difficulty::Easy => Difficulty::Normal,
+			Difficulty::Normal => Difficulty::Hard,
+			Difficulty::Hard => Difficulty::Easy,
+		}
+	}
+
+	pub fn parse(name: &str) -> Option<Difficulty> {
+		[Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+			.into_iter()
+			.find(|difficulty| difficulty.name().eq_ignore_ascii_case(name))
+	}
+}
+
+/// A team buddy outside of the ECS, for teams that are shopped for without a window
+pub struct ShopBuddy {
+	pub face: BuddyFace,
+	pub color: BuddyColor,
+	pub health: Health,
+	pub strength: Strength,
+	pub level: Level,
+	pub shielded: bool,
+	pub faint_buff: Option<FaintBuff>,
+}
+
+impl ShopBuddy {
+	pub fn new(template: BuddyTemplate) -> Self {
+		Self {
+			face: template.face,
+			color: template.color,
+			health: Health(Attribute::new(template.health)),
+			strength: Strength(Attribute::new(template.strength)),
+			level: Level::default(),
+			shielded: false,
+			faint_buff: None,
+		}
+	}
+
+	pub fn base_cost(&self) -> usize {
+		self.health.0.base() + self.strength.0.base()
+	}
+
+	pub fn record(&self, slot: usize) -> BuddyRecord {
+		BuddyRecord {
+			slot,
+			face: self.face,
+			color: self.color,
+			health: self.health.0.value(),
+			strength: self.strength.0.value(),
+			level: self.level.level,
+			experience: self.level.experience,
+			shielded: self.shielded,
+			faint_buff: self.faint_buff,
+		}
+	}
+}
+
+pub fn records(team: &[ShopBuddy]) -> Vec<BuddyRecord> {
+	team.iter().enumerate().map(|(slot, buddy)| buddy.record(slot)).collect()
+}
+
+/// Builds an opponent by shopping from scratch with `budget` coins, which should be what the
+/// player has spent so far so both teams had the same economy to work with. A budget too small
+/// for `MIN_TEAM_SIZE` buddies is topped up with free ones from the round's tiers.
+pub fn build_opponent(
+	rng: &mut impl Rng,
+	balance: &BalanceConfig,
+	difficulty: Difficulty,
+	round: usize,
+	team_size: usize,
+	budget: usize,
+	player: &[BuddyRecord],
+) -> Vec<BuddyRecord> {
+	let mut coins = Coins(budget);
+	let mut team = Vec::new();
+	visit_shop(rng, balance, difficulty, round, team_size, &mut coins, &mut team);
+	while team.len() < MIN_TEAM_SIZE.min(team_size) {
+		let tier = balance.roll_tier(rng, round);
+		team.push(ShopBuddy::new(BuddyTemplate::random_for_tier(rng, tier)));
+	}
+	if difficulty == Difficulty::Hard {
+		counter_lineup(&mut team, player);
+	}
+	records(&team)
+}
+
+/// Spends `coins` in one visit to the shop, rerolling a few times while there's money left.
+/// Returns the coins paid, not counting refunds from selling.
+pub fn visit_shop(
+	rng: &mut impl Rng,
+	balance: &BalanceConfig,
+	difficulty: Difficulty,
+	round: usize,
+	team_size: usize,
+	coins: &mut Coins,
+	team: &mut Vec<ShopBuddy>,
+) -> usize {
+	let mut spent = 0;
+	for reroll in 0..=MAX_REROLLS {
+		let mut offers = (0..SHOP_BUDDY_SLOTS)
+			.map(|_| {
+				let tier = balance.roll_tier(rng, round);
+				BuddyTemplate::random_for_tier(rng, tier)
+			})
+			.collect::<Vec<_>>();
+		// strongest offers get the first pick
+		offers.sort_by_key(|offer| std::cmp::Reverse(offer.base_cost()));
+		for offer in offers {
+			spent += buy_offer(balance, difficulty, team_size, coins, team, offer);
+		}
+
+		if difficulty != Difficulty::Easy {
+			for _ in 0..SHOP_ITEMS {
+				let item = Item::random(rng);
+				spent += buy_item(balance, coins, team, item);
+			}
+			lineup(team);
+		}
+
+		let cheapest = balance.price.min.min(balance.item_price);
+		if reroll == MAX_REROLLS || coins.0 < balance.reroll_cost + cheapest {
+			break;
+		}
+		coins.0 -= balance.reroll_cost;
+		spent += balance.reroll_cost;
+	}
+	spent
+}
+
+fn buy_offer(
+	balance: &BalanceConfig,
+	difficulty: Difficulty,
+	team_size: usize,
+	coins: &mut Coins,
+	team: &mut Vec<ShopBuddy>,
+	offer: BuddyTemplate,
+) -> usize {
+	let price = balance.price(offer.base_cost());
+	if coins.0 < price {
+		return 0;
+	}
+
+	let merge_target = team.iter_mut().find(|buddy| {
+		same_species((&buddy.face, &buddy.color), (&offer.face, &offer.color))
+			&& !buddy.level.is_max()
+	});
+	if let (Some(target), true) = (merge_target, difficulty != Difficulty::Easy) {
+		merge_buddy(
+			&mut target.health,
+			&mut target.strength,
+			&mut target.level,
+			offer.health,
+			offer.strength,
+		);
+		coins.0 -= price;
+		price
+	} else if team.len() < team_size {
+		team.push(ShopBuddy::new(offer));
+		coins.0 -= price;
+		price
+	} else if let Some((weakest, buddy)) =
+		team.iter().enumerate().min_by_key(|(_, buddy)| buddy.base_cost())
+	{
+		let refund = sell_value(balance, &buddy.level, &buddy.health, &buddy.strength);
+		if offer.base_cost() > buddy.base_cost() + UPGRADE_MARGIN && coins.0 + refund >= price {
+			coins.0 = coins.0 + refund - price;
+			team[weakest] = ShopBuddy::new(offer);
+			return price;
+		}
+		0
+	} else {
+		0
+	}
+}
+
+/// Stat items go to the weakest buddy, shields and honey to whoever fights first
+fn buy_item(
+	balance: &BalanceConfig,
+	coins: &mut Coins,
+	team: &mut [ShopBuddy],
+	item: Item,
+) -> usize {
+	if coins.0 < balance.item_price || team.is_empty() {
+		return 0;
+	}
+
+	let weakest = (0..team.len()).min_by_key(|i| team[*i].base_cost()).unwrap();
+	let used = match item {
+		Item::Apple => {
+			let base = team[weakest].health.0.base();
+			team[weakest].health.0.set_base(base + 1);
+			true
+		},
+		Item::Meat => {
+			let base = team[weakest].strength.0.base();
+			team[weakest].strength.0.set_base(base + 1);
+			true
+		},
+		Item::Pepper => {
+			team[weakest].strength.0.add(3);
+			true
+		},
+		Item::Shield if !team[0].shielded => {
+			team[0].shielded = true;
+			true
+		},
+		Item::Honey if team[0].faint_buff.is_none() => {
+			team[0].faint_buff = Some(FaintBuff { health: 2, strength: 2 });
+			true
+		},
+		_ => false,
+	};
+	if used {
+		coins.0 -= balance.item_price;
+		balance.item_price
+	} else {
+		0
+	}
+}
+
+/// Sturdy buddies go up front to soak hits, anything carrying honey leads so its buff isn't
+/// wasted on an empty slot
+fn lineup(team: &mut [ShopBuddy]) {
+	team.sort_by_key(|buddy| {
+		(
+			buddy.faint_buff.is_none(),
+			std::cmp::Reverse(buddy.health.0.value() + buddy.shielded as usize),
+		)
+	});
+}
+
+/// Tries every order of the team against the player's lineup and keeps the best one
+fn counter_lineup(team: &mut Vec<ShopBuddy>, player: &[BuddyRecord]) {
+	let rank = |outcome| match outcome {
+		// the opponent fights on the right, so the player losing is a win here
+		BattleOutcome::Lose => 2,
+		BattleOutcome::Tie => 1,
+		BattleOutcome::Win => 0,
+	};
+
+	let mut best = (0..team.len()).collect::<Vec<_>>();
+	let mut best_rank = rank(fight(player, &lineup_records(team, &best)).outcome);
+	let mut order = best.clone();
+	while best_rank < 2 && next_permutation(&mut order) {
+		let order_rank = rank(fight(player, &lineup_records(team, &order)).outcome);
+		if order_rank > best_rank {
+			best_rank = order_rank;
+			best = order.clone();
+		}
+	}
+
+	let mut buddies = team.drain(..).map(Some).collect::<Vec<_>>();
+	team.extend(best.into_iter().filter_map(|i| buddies[i].take()));
+}
+
+fn lineup_records(team: &[ShopBuddy], order: &[usize]) -> Vec<BuddyRecord> {
+	order.iter().enumerate().map(|(slot, i)| team[*i].record(slot)).collect()
+}
+
+/// Steps to the next lexicographic permutation, returns false after the last one
+fn next_permutation(order: &mut [usize]) -> bool {
+	let pivot = match (1..order.len()).rev().find(|i| order[i - 1] < order[*i]) {
+		Some(i) => i - 1,
+		None => return false,
+	};
+	let swap = (pivot + 1..order.len()).rev().find(|i| order[*i] > order[pivot]).unwrap();
+	order.swap(pivot, swap);
+	order[pivot + 1..].reverse();
+	true
+}
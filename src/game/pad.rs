@@ -1,9 +1,11 @@
 use crate::{
 	game::{
-		animate::{AnimateRange, Ease},
-		buddy::{Side, Slot},
+		battle::BattleSpeed,
+		buddy::{Side, Slot, TeamSize},
 		Z_PAD,
 	},
+	tween::{Ease, Tween, Tweenable, ValueLens},
+	view::View,
 	AppState,
 };
 use bevy::prelude::*;
@@ -11,10 +13,16 @@ use std::time::Duration;
 
 pub const PAD_SPACING: f32 = 180.0;
 const SIDE_SPACING: f32 = 120.0;
-const RIGHT_PAD_OUT: f32 = 1500.0;
-const PAD_CENTER_OFFSET: f32 = ((Slot::MAX_PER_SIDE - 1) as f32 * PAD_SPACING) / 2.0;
 const SHOP_PAD_OFFSET: f32 = -200.0;
-const SHOP_PAD_OUT: f32 = -800.0;
+// how far past the edge of the view pads wait while they're out, enough to hide their buddy
+const PAD_OUT_MARGIN: f32 = 200.0;
+// room left past the outermost pad when framing the camera
+const FRAME_MARGIN: f32 = 80.0;
+
+/// Offset that centers a team of `team_size` pads on the screen
+pub fn pad_center_offset(team_size: usize) -> f32 {
+	(team_size.max(1) - 1) as f32 * PAD_SPACING / 2.0
+}
 
 #[derive(Bundle, Default)]
 pub struct PadBundle {
@@ -25,66 +33,53 @@ pub struct PadBundle {
 	pub global_transform: GlobalTransform,
 }
 
-#[derive(Component)]
+#[derive(Component, Default)]
 pub struct Pad {
-	right_animate_in: AnimateRange,
-	right_animate_out: AnimateRange,
-	left_animate_center: AnimateRange,
-	left_animate_side: AnimateRange,
-	shop_animate_in: AnimateRange,
-	shop_animate_out: AnimateRange,
+	// how far the pad is from its shop place (0.0) to its battle place (1.0), so the layout can
+	// follow the team size and the view's size while it slides
+	layout: f32,
+	slide: Option<Tween<f32>>,
 }
 
-impl Default for Pad {
-	fn default() -> Self {
-		let mut value = Self {
-			right_animate_out: AnimateRange::new(
-				Duration::from_secs_f32(1.5),
-				Ease::InOutCirc,
-				SIDE_SPACING..RIGHT_PAD_OUT,
-				false,
-			),
-			right_animate_in: AnimateRange::new(
-				Duration::from_secs_f32(2.0),
-				Ease::InOutCirc,
-				RIGHT_PAD_OUT..SIDE_SPACING,
-				false,
-			),
-			left_animate_center: AnimateRange::new(
-				Duration::from_secs_f32(1.5),
-				Ease::InOutCirc,
-				-SIDE_SPACING..PAD_CENTER_OFFSET,
-				false,
-			),
-			left_animate_side: AnimateRange::new(
-				Duration::from_secs_f32(2.0),
-				Ease::InOutCirc,
-				PAD_CENTER_OFFSET..-SIDE_SPACING,
-				false,
-			),
-			shop_animate_out: AnimateRange::new(
-				Duration::from_secs_f32(1.5),
-				Ease::InOutCirc,
-				SHOP_PAD_OFFSET..SHOP_PAD_OUT,
-				false,
-			),
-			shop_animate_in: AnimateRange::new(
-				Duration::from_secs_f32(1.5),
-				Ease::InOutCirc,
-				SHOP_PAD_OUT..SHOP_PAD_OFFSET,
-				false,
-			),
-		};
-		value.right_animate_out.set_percent(1.0);
-		value.left_animate_center.set_percent(1.0);
-		value
+impl Pad {
+	fn slide_to(&mut self, layout: f32, seconds: f32) {
+		self.slide = Some(Tween::new(
+			Duration::from_secs_f32(seconds),
+			Ease::InOutCirc,
+			ValueLens { start: self.layout, end: layout },
+		));
 	}
 }
 
-pub fn spawn_pads(commands: &mut Commands, asset_server: &AssetServer) {
-	for i in 0..Slot::MAX_PER_SIDE {
-		spawn_pad(commands, asset_server, Side::Left, Slot::new(i));
-		spawn_pad(commands, asset_server, Side::Right, Slot::new(i));
+/// Keeps one Left and one Right pad per team slot whenever the team size changes
+pub fn sync_team_pads(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	team_size: Res<TeamSize>,
+	pads: Query<(Entity, &Side, &Slot), With<Pad>>,
+) {
+	if !team_size.is_changed() {
+		return;
+	}
+
+	let mut existing = Vec::new();
+	for (entity, side, slot) in pads.iter() {
+		if *side == Side::Shop {
+			continue;
+		}
+		if slot.base >= team_size.0 {
+			commands.entity(entity).despawn_recursive();
+		} else {
+			existing.push((*side, slot.base));
+		}
+	}
+
+	for i in 0..team_size.0 {
+		for side in [Side::Left, Side::Right] {
+			if !existing.contains(&(side, i)) {
+				spawn_pad(&mut commands, &asset_server, side, Slot::new(i));
+			}
+		}
 	}
 }
 
@@ -92,45 +87,58 @@ pub fn spawn_pad(commands: &mut Commands, asset_server: &AssetServer, side: Side
 	commands
 		.spawn_bundle(PadBundle { side, slot, ..Default::default() })
 		.with_children(|parent| {
-			parent.spawn_bundle(SpriteBundle {
-				texture: asset_server.load("pad.png"),
-				transform: Transform::from_xyz(0., -60., Z_PAD),
-				..Default::default()
-			});
+			parent
+				.spawn_bundle(SpriteBundle {
+					texture: asset_server.load("pad.png"),
+					transform: Transform::from_xyz(0., -60., Z_PAD),
+					..Default::default()
+				})
+				.insert(PadSprite);
 		});
 }
 
+/// The pad's visible sprite, tinted to show where something can be dropped
+#[derive(Component)]
+pub struct PadSprite;
+
 pub fn position_pad(
 	time: Res<Time>,
 	state: Res<State<AppState>>,
+	team_size: Res<TeamSize>,
+	speed: Res<BattleSpeed>,
+	view: Res<View>,
 	mut pads: Query<(&mut Pad, &mut Transform, &Side, &Slot)>,
 ) {
+	let center_offset = pad_center_offset(team_size.0);
+	let right_out = view.half_size().x + PAD_OUT_MARGIN;
+	let shop_out = -(view.half_size().y + PAD_OUT_MARGIN);
+	let lerp = |from: f32, to: f32, amount: f32| from + (to - from) * amount;
+	let delta = if state.current().is_battle() { speed.delta(time.delta()) } else { time.delta() };
 	for (mut pad, mut transform, side, slot) in pads.iter_mut() {
+		let Pad { layout, slide } = &mut *pad;
+		if let Some(tween) = slide {
+			if tween.tick(delta, layout).is_some() {
+				*slide = None;
+			}
+		}
+		let layout = *layout;
+
+		// the left side moves beside the center line, the right side comes in from out of view
+		// and the shop side drops out of view
 		let side_sign;
 		let offset = match side {
 			Side::Left => {
 				side_sign = -1.0;
-				if *state.current() == AppState::Battle {
-					Vec2::new(pad.left_animate_side.tick(time.delta()), 0.0)
-				} else {
-					Vec2::new(pad.left_animate_center.tick(time.delta()), 0.0)
-				}
+				let centered = 1.0 - layout;
+				Vec2::new(-SIDE_SPACING + (center_offset + SIDE_SPACING) * centered, 0.0)
 			},
 			Side::Right => {
 				side_sign = 1.0;
-				if *state.current() == AppState::Battle {
-					Vec2::new(pad.right_animate_in.tick(time.delta()), 0.0)
-				} else {
-					Vec2::new(pad.right_animate_out.tick(time.delta()), 0.0)
-				}
+				Vec2::new(lerp(SIDE_SPACING, right_out, 1.0 - layout), 0.0)
 			},
 			Side::Shop => {
 				side_sign = -1.0;
-				if *state.current() == AppState::Battle {
-					Vec2::new(PAD_CENTER_OFFSET, pad.shop_animate_out.tick(time.delta()))
-				} else {
-					Vec2::new(PAD_CENTER_OFFSET, pad.shop_animate_in.tick(time.delta()))
-				}
+				Vec2::new(center_offset, lerp(SHOP_PAD_OFFSET, shop_out, layout))
 			},
 		};
 
@@ -139,22 +147,24 @@ pub fn position_pad(
 	}
 }
 
-pub fn pad_exit_battle(mut pads: Query<(&mut Pad, &Side)>) {
-	for (mut pad, side) in pads.iter_mut() {
-		match side {
-			Side::Left => pad.left_animate_center.reset(),
-			Side::Right => pad.right_animate_out.reset(),
-			Side::Shop => pad.shop_animate_in.reset(),
-		}
+pub fn pad_exit_battle(mut pads: Query<&mut Pad>) {
+	for mut pad in pads.iter_mut() {
+		pad.slide_to(0.0, 1.5);
 	}
 }
 
 pub fn pad_enter_battle(mut pads: Query<(&mut Pad, &Side)>) {
 	for (mut pad, side) in pads.iter_mut() {
-		match side {
-			Side::Left => pad.left_animate_side.reset(),
-			Side::Right => pad.right_animate_in.reset(),
-			Side::Shop => pad.shop_animate_out.reset(),
-		}
+		let seconds = if *side == Side::Shop { 1.5 } else { 2.0 };
+		pad.slide_to(1.0, seconds);
 	}
 }
+
+/// Zooms the view out so teams bigger than the default still fit
+pub fn frame_camera(time: Res<Time>, team_size: Res<TeamSize>, mut view: ResMut<View>) {
+	// pads are furthest from the center during battles, when both teams sit side by side
+	let half_width = SIDE_SPACING + (team_size.0.max(1) - 1) as f32 * PAD_SPACING + FRAME_MARGIN;
+	let target = (half_width / (view.virtual_size.x / 2.0)).max(1.0);
+	let amount = (2.0 * time.delta_seconds()).min(1.0);
+	view.zoom += (target - view.zoom) * amount;
+}
@@ -0,0 +1,217 @@
+use crate::{
+	game::{
+		buddy::{Buddy, BuddyColor, BuddyFace, Health, Level, Side, Slot, Strength},
+		item::{FaintBuff, Shielded, ShopItem},
+		pad::Pad,
+		replay::BuddyRecord,
+		shop::BuddyDragState,
+		ui::UiRoot,
+		BattleMessages, Foreground,
+	},
+	network::ShouldSaveGame,
+	widget::{spawn_button, ButtonPressed},
+	AppState,
+};
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<SavedTeam>()
+			.add_system_set(SystemSet::on_update(AppState::Shop).with_system(pause_game))
+			.add_system_set(SystemSet::on_update(AppState::Battle).with_system(pause_game))
+			.add_system_set(SystemSet::on_update(AppState::Replay).with_system(pause_game))
+			.add_system_set(SystemSet::on_enter(AppState::Paused).with_system(spawn_pause_menu))
+			// settings are pushed on top of the pause menu and come back to it
+			.add_system_set(SystemSet::on_resume(AppState::Paused).with_system(spawn_pause_menu))
+			.add_system_set(SystemSet::on_update(AppState::Paused).with_system(pause_menu))
+			.add_system_set(SystemSet::on_pause(AppState::Paused).with_system(cleanup_pause_menu))
+			.add_system_set(SystemSet::on_exit(AppState::Paused).with_system(cleanup_pause_menu))
+			.add_system_set(SystemSet::on_enter(AppState::Menu).with_system(teardown_game));
+	}
+}
+
+/// The team as it was when the game was left for the menu, `setup_game` brings it back
+#[derive(Default)]
+pub struct SavedTeam(pub Vec<BuddyRecord>);
+
+/// What a buddy is saved from, see `team_records`
+pub type TeamBuddy<'a> = (
+	&'a Side,
+	&'a Slot,
+	&'a BuddyFace,
+	&'a BuddyColor,
+	&'a Health,
+	&'a Strength,
+	&'a Level,
+	Option<&'a Shielded>,
+	Option<&'a FaintBuff>,
+);
+
+/// The player's team at base stats, a battle left halfway may have knocked some off
+pub fn team_records<'a>(team: impl Iterator<Item = TeamBuddy<'a>>) -> Vec<BuddyRecord> {
+	let mut records = Vec::new();
+	for (side, slot, face, color, health, strength, level, shielded, faint_buff) in team {
+		if *side == Side::Left {
+			records.push(BuddyRecord {
+				slot: slot.base,
+				face: *face,
+				color: *color,
+				health: health.0.base(),
+				strength: strength.0.base(),
+				level: level.level,
+				experience: level.experience,
+				shielded: shielded.is_some(),
+				faint_buff: faint_buff.copied(),
+			});
+		}
+	}
+	records
+}
+
+struct PauseMenuData {
+	root: Entity,
+}
+
+#[derive(Component, Clone, Copy)]
+pub enum PauseButton {
+	Resume,
+	Settings,
+	Quit,
+}
+
+/// Run criteria for systems that animate over time, so everything holds still while paused
+pub fn unpaused(state: Res<State<AppState>>) -> ShouldRun {
+	let paused =
+		*state.current() == AppState::Paused || state.inactives().contains(&AppState::Paused);
+	if paused {
+		ShouldRun::No
+	} else {
+		ShouldRun::Yes
+	}
+}
+
+// escape or the gamepad's start button, consumed so the state entered this frame doesn't see it
+fn pause_pressed(
+	keys: &mut Input<KeyCode>,
+	gamepads: &Gamepads,
+	gamepad_buttons: &mut Input<GamepadButton>,
+) -> bool {
+	let mut pressed = keys.clear_just_pressed(KeyCode::Escape);
+	for gamepad in gamepads.iter() {
+		pressed |=
+			gamepad_buttons.clear_just_pressed(GamepadButton(*gamepad, GamepadButtonType::Start));
+	}
+	pressed
+}
+
+fn pause_game(
+	mut state: ResMut<State<AppState>>,
+	mut keys: ResMut<Input<KeyCode>>,
+	gamepads: Res<Gamepads>,
+	mut gamepad_buttons: ResMut<Input<GamepadButton>>,
+) {
+	if pause_pressed(&mut keys, &gamepads, &mut gamepad_buttons) {
+		// pushed so the shop or battle underneath picks up where it left off
+		state.push(AppState::Paused).unwrap();
+	}
+}
+
+fn spawn_pause_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+	let root = commands
+		.spawn_bundle(NodeBundle {
+			style: Style {
+				size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+				position_type: PositionType::Absolute,
+				// ui is laid out bottom to top, so the title comes first
+				flex_direction: FlexDirection::ColumnReverse,
+				justify_content: JustifyContent::Center,
+				align_items: AlignItems::Center,
+				..Default::default()
+			},
+			color: Color::rgba(1.0, 1.0, 1.0, 0.8).into(),
+			..Default::default()
+		})
+		.with_children(|parent| {
+			parent.spawn_bundle(TextBundle {
+				text: Text::with_section(
+					"Paused",
+					TextStyle {
+						font: asset_server.load("font/CaveatBrush-Regular.ttf"),
+						font_size: 120.0,
+						color: Color::hex("323232").unwrap(),
+					},
+					Default::default(),
+				),
+				..Default::default()
+			});
+			spawn_button(parent, &asset_server, "Resume", 250.0, PauseButton::Resume);
+			spawn_button(parent, &asset_server, "Settings", 250.0, PauseButton::Settings);
+			spawn_button(parent, &asset_server, "Save & quit to menu", 250.0, PauseButton::Quit);
+		})
+		.id();
+
+	commands.insert_resource(PauseMenuData { root });
+}
+
+fn pause_menu(
+	mut state: ResMut<State<AppState>>,
+	mut save_game: ResMut<ShouldSaveGame>,
+	mut keys: ResMut<Input<KeyCode>>,
+	gamepads: Res<Gamepads>,
+	mut gamepad_buttons: ResMut<Input<GamepadButton>>,
+	mut pressed: EventReader<ButtonPressed>,
+	buttons: Query<&PauseButton>,
+) {
+	if pause_pressed(&mut keys, &gamepads, &mut gamepad_buttons) {
+		state.pop().unwrap();
+		return;
+	}
+
+	for ButtonPressed(button) in pressed.iter() {
+		match buttons.get(*button) {
+			Ok(PauseButton::Resume) => state.pop().unwrap(),
+			Ok(PauseButton::Settings) => state.push(AppState::Settings).unwrap(),
+			Ok(PauseButton::Quit) => {
+				save_game.0 = true;
+				// unwinds the whole stack, so the shop and battle clean up after themselves
+				state.replace(AppState::Menu).unwrap();
+			},
+			Err(_) => continue,
+		}
+		return;
+	}
+}
+
+fn cleanup_pause_menu(mut commands: Commands, pause_menu: Res<PauseMenuData>) {
+	commands.entity(pause_menu.root).despawn_recursive();
+}
+
+/// Despawns what `setup_game` and the shop spawned, so coming back from the menu starts clean.
+/// The team is kept aside to be spawned again.
+fn teardown_game(
+	mut commands: Commands,
+	battle_messages: Option<Res<BattleMessages>>,
+	mut saved_team: ResMut<SavedTeam>,
+	mut buddy_drag_state: ResMut<BuddyDragState>,
+	team: Query<TeamBuddy, With<Buddy>>,
+	game_entities: Query<
+		Entity,
+		Or<(With<Buddy>, With<Pad>, With<ShopItem>, With<UiRoot>, With<Foreground>)>,
+	>,
+) {
+	// nothing to tear down the first time the menu shows up
+	let battle_messages = match battle_messages {
+		Some(battle_messages) => battle_messages,
+		None => return,
+	};
+
+	saved_team.0 = team_records(team.iter());
+
+	for entity in game_entities.iter().chain(battle_messages.entities()) {
+		commands.entity(entity).despawn_recursive();
+	}
+	commands.remove_resource::<BattleMessages>();
+	*buddy_drag_state = BuddyDragState::None;
+}
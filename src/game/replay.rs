@@ -0,0 +1,420 @@
+use crate::{
+	game::{
+		battle::{
+			battle, despawn_speed_buttons, spawn_speed_buttons, speed_buttons, Battle, BattleMode,
+			BattleSpeed,
+		},
+		buddy::{Buddy, BuddyColor, BuddyFace, BuddyTemplate, Level, Side, TeamSize},
+		item::{add_faint_buff, add_shield, FaintBuff},
+		pad::{pad_enter_battle, pad_exit_battle, position_pad, sync_team_pads},
+		sell::set_visibility_recursive,
+		skin::SkinHidden,
+		ui::UiRoot,
+		BattleMessages,
+	},
+	network::Network,
+	settings::Settings,
+	types::NetworkMessage,
+	widget::{set_button_label, spawn_game_button, ButtonPressed},
+	AppState,
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<LastBattle>()
+			.add_system_set(SystemSet::on_exit(AppState::Battle).with_system(store_battle_log))
+			.add_system_set(SystemSet::on_enter(AppState::Shop).with_system(spawn_replay_button))
+			.add_system_set(SystemSet::on_update(AppState::Shop).with_system(replay_button))
+			.add_system_set(SystemSet::on_pause(AppState::Shop).with_system(hide_replay_button))
+			.add_system_set(SystemSet::on_exit(AppState::Shop).with_system(despawn_replay_button))
+			.add_system_set(
+				SystemSet::on_enter(AppState::Replay)
+					.with_system(pad_enter_battle)
+					.with_system(enter_replay)
+					.with_system(spawn_speed_buttons),
+			)
+			.add_system_set(
+				SystemSet::on_update(AppState::Replay)
+					.with_system(battle)
+					.with_system(position_pad)
+					.with_system(sync_team_pads)
+					.with_system(speed_buttons)
+					.with_system(replay_controls),
+			)
+			.add_system_set(
+				SystemSet::on_exit(AppState::Replay)
+					.with_system(pad_exit_battle)
+					.with_system(exit_replay)
+					.with_system(despawn_speed_buttons),
+			);
+	}
+}
+
+const REPLAY_DIR: &str = "replays";
+const REPLAY_PATH: &str = "replays/last_battle.json";
+
+/// Everything needed to play a battle back: the teams as they lined up and every step that
+/// followed
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct BattleLog {
+	/// Seed the opponent team was generated from
+	pub seed: u64,
+	pub round: usize,
+	pub left: Vec<BuddyRecord>,
+	pub right: Vec<BuddyRecord>,
+	pub steps: Vec<BattleStep>,
+}
+
+/// A buddy as it entered the battle, with any temporary buffs already counted in its stats
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BuddyRecord {
+	pub slot: usize,
+	pub face: BuddyFace,
+	pub color: BuddyColor,
+	pub health: usize,
+	pub strength: usize,
+	pub level: usize,
+	/// Towards the next level, missing from replays and runs saved before it was kept
+	#[serde(default)]
+	pub experience: usize,
+	pub shielded: bool,
+	pub faint_buff: Option<FaintBuff>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum BattleStep {
+	/// The front buddies trade blows. A blocked hit is absorbed by the target's shield.
+	Attack {
+		left_damage: usize,
+		right_damage: usize,
+		left_blocked: bool,
+		right_blocked: bool,
+	},
+	/// The front buddy of `side` fainted, passing on its on-faint buff if it had one
+	Faint {
+		side: Side,
+		buff: Option<FaintBuff>,
+	},
+	Result(BattleOutcome),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub enum BattleOutcome {
+	Win,
+	Lose,
+	Tie,
+}
+
+impl BattleOutcome {
+	pub fn new(left_alive: bool, right_alive: bool) -> Self {
+		match (left_alive, right_alive) {
+			(true, false) => BattleOutcome::Win,
+			(false, true) => BattleOutcome::Lose,
+			_ => BattleOutcome::Tie,
+		}
+	}
+}
+
+/// The most recent battle. It is saved to disk so it can still be replayed after a restart.
+pub struct LastBattle(pub Option<BattleLog>);
+
+impl Default for LastBattle {
+	fn default() -> Self {
+		if cfg!(target_arch = "wasm32") {
+			return Self(None);
+		}
+		let log = std::fs::read_to_string(REPLAY_PATH).ok().and_then(|json| {
+			serde_json::from_str(&json)
+				.map_err(|err| warn!("ignoring unreadable {}: {}", REPLAY_PATH, err))
+				.ok()
+		});
+		Self(log)
+	}
+}
+
+/// Keeps the battle that was just fought for replaying. A battle left halfway through the pause
+/// menu has no result yet and doesn't replace the last finished one.
+fn store_battle_log(
+	battle: Res<Battle>,
+	settings: Res<Settings>,
+	mut last_battle: ResMut<LastBattle>,
+	mut network: ResMut<Network>,
+) {
+	if !battle.finished() {
+		return;
+	}
+	match serde_json::to_string(&battle.log) {
+		Ok(json) => {
+			if !cfg!(target_arch = "wasm32") {
+				let saved = std::fs::create_dir_all(REPLAY_DIR)
+					.and_then(|_| std::fs::write(REPLAY_PATH, &json));
+				if let Err(err) = saved {
+					error!("failed to save {}: {}", REPLAY_PATH, err);
+				}
+			}
+			if settings.upload_replays {
+				network.send_message(&NetworkMessage::UploadReplay(json));
+			}
+		},
+		Err(err) => error!("failed to encode battle log: {}", err),
+	}
+	last_battle.0 = Some(battle.log.clone());
+}
+
+#[derive(Component)]
+pub struct ReplayButton;
+
+fn spawn_replay_button(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	ui_root: Query<Entity, With<UiRoot>>,
+) {
+	commands.entity(ui_root.single()).with_children(|parent| {
+		let style = Style {
+			size: Size::new(Val::Px(150.0), Val::Px(65.0)),
+			position_type: PositionType::Absolute,
+			position: Rect { right: Val::Px(100.0), bottom: Val::Px(190.0), ..Default::default() },
+			display: Display::None,
+			..Default::default()
+		};
+		spawn_game_button(parent, &asset_server, "Replay", style, ReplayButton);
+	});
+}
+
+fn despawn_replay_button(mut commands: Commands, buttons: Query<Entity, With<ReplayButton>>) {
+	for entity in buttons.iter() {
+		commands.entity(entity).despawn_recursive();
+	}
+}
+
+fn hide_replay_button(mut buttons: Query<&mut Style, With<ReplayButton>>) {
+	for mut style in buttons.iter_mut() {
+		style.display = Display::None;
+	}
+}
+
+fn replay_button(
+	mut state: ResMut<State<AppState>>,
+	last_battle: Res<LastBattle>,
+	mut pressed: EventReader<ButtonPressed>,
+	mut buttons: Query<&mut Style, With<ReplayButton>>,
+) {
+	for ButtonPressed(button) in pressed.iter() {
+		if buttons.get(*button).is_ok() && last_battle.0.is_some() {
+			// the shop is paused rather than left so its offers survive the replay
+			state.push(AppState::Replay).unwrap();
+		}
+	}
+
+	for mut style in buttons.iter_mut() {
+		let display = if last_battle.0.is_some() { Display::Flex } else { Display::None };
+		if style.display != display {
+			style.display = display;
+		}
+	}
+}
+
+/// Marks the buddies spawned from the log, they only live as long as the replay
+#[derive(Component)]
+pub struct ReplayBuddy;
+
+/// The player's team sits out the replay without a side, hidden until it is over
+#[derive(Component)]
+pub struct StashedBuddy;
+
+pub struct ReplayState {
+	controls: Entity,
+	team_size: usize,
+}
+
+#[derive(Component, Clone, Copy)]
+pub enum ReplayControl {
+	Pause,
+	Step,
+	Exit,
+}
+
+impl ReplayControl {
+	fn label(&self) -> String {
+		match self {
+			ReplayControl::Pause => "Pause".to_string(),
+			ReplayControl::Step => "Step".to_string(),
+			ReplayControl::Exit => "Exit".to_string(),
+		}
+	}
+}
+
+fn enter_replay(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	last_battle: Res<LastBattle>,
+	mut battle: ResMut<Battle>,
+	mut team_size: ResMut<TeamSize>,
+	ui_root: Query<Entity, With<UiRoot>>,
+	team: Query<(Entity, &Side), With<Buddy>>,
+	children: Query<&Children>,
+	mut visibilities: Query<&mut Visibility, Without<SkinHidden>>,
+) {
+	for (entity, side) in team.iter() {
+		if *side == Side::Left {
+			commands.entity(entity).remove::<Side>().insert(StashedBuddy);
+			set_visibility_recursive(entity, false, &children, &mut visibilities);
+		}
+	}
+
+	let log = last_battle.0.clone().unwrap_or_default();
+	for (side, records) in [(Side::Left, &log.left), (Side::Right, &log.right)] {
+		for record in records {
+			let buddy = spawn_recorded_buddy(&mut commands, &asset_server, record, side);
+			commands.entity(buddy).insert(ReplayBuddy);
+		}
+	}
+
+	let replay_size = log.left.len().max(log.right.len()).clamp(TeamSize::MIN, TeamSize::MAX);
+	let previous_size = team_size.0;
+	if team_size.0 != replay_size {
+		team_size.0 = replay_size;
+	}
+
+	let controls = spawn_replay_controls(&mut commands, &asset_server, ui_root.single());
+	commands.insert_resource(ReplayState { controls, team_size: previous_size });
+	battle.begin(log, BattleMode::Replay);
+}
+
+/// Spawns a buddy exactly as it was recorded, level and items included
+pub fn spawn_recorded_buddy(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	record: &BuddyRecord,
+	side: Side,
+) -> Entity {
+	let template = BuddyTemplate {
+		face: record.face,
+		health: record.health,
+		strength: record.strength,
+		color: record.color,
+	};
+	let buddy = template.spawn(commands, asset_server, record.slot, side, Transform::default());
+	commands
+		.entity(buddy)
+		.insert(Level { level: record.level, experience: record.experience });
+	if record.shielded {
+		add_shield(commands, asset_server, buddy);
+	}
+	if let Some(faint_buff) = record.faint_buff {
+		add_faint_buff(commands, asset_server, buddy, faint_buff);
+	}
+	buddy
+}
+
+fn spawn_replay_controls(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	ui_root: Entity,
+) -> Entity {
+	let controls = [ReplayControl::Pause, ReplayControl::Step, ReplayControl::Exit];
+	let mut root = None;
+	commands.entity(ui_root).with_children(|parent| {
+		root = Some(
+			parent
+				.spawn_bundle(NodeBundle {
+					style: Style {
+						position_type: PositionType::Absolute,
+						position: Rect {
+							right: Val::Px(100.0),
+							// above the speed buttons shared with live battles
+							bottom: Val::Px(115.0),
+							..Default::default()
+						},
+						..Default::default()
+					},
+					color: Color::NONE.into(),
+					..Default::default()
+				})
+				.with_children(|parent| {
+					for control in controls {
+						let style = Style {
+							size: Size::new(Val::Px(90.0), Val::Px(65.0)),
+							margin: Rect { left: Val::Px(10.0), ..Default::default() },
+							..Default::default()
+						};
+						spawn_game_button(parent, asset_server, control.label(), style, control);
+					}
+				})
+				.id(),
+		);
+	});
+
+	root.unwrap()
+}
+
+fn replay_controls(
+	mut state: ResMut<State<AppState>>,
+	mut speed: ResMut<BattleSpeed>,
+	mut pressed: EventReader<ButtonPressed>,
+	controls: Query<(&ReplayControl, &Children)>,
+	mut texts: Query<&mut Text>,
+) {
+	for ButtonPressed(button) in pressed.iter() {
+		match controls.get(*button) {
+			Ok((ReplayControl::Pause, _)) => speed.paused = !speed.paused,
+			Ok((ReplayControl::Step, _)) => {
+				speed.paused = true;
+				speed.step = true;
+			},
+			Ok((ReplayControl::Exit, _)) => {
+				// the replay may have just finished and popped itself this frame
+				state.pop().ok();
+			},
+			Err(_) => {},
+		}
+	}
+
+	if speed.is_changed() {
+		for (control, children) in controls.iter() {
+			if let ReplayControl::Pause = control {
+				let label = if speed.paused { "Play" } else { "Pause" };
+				set_button_label(children, &mut texts, label);
+			}
+		}
+	}
+}
+
+fn exit_replay(
+	mut commands: Commands,
+	replay_state: Res<ReplayState>,
+	battle_messages: Res<BattleMessages>,
+	mut battle: ResMut<Battle>,
+	mut speed: ResMut<BattleSpeed>,
+	mut team_size: ResMut<TeamSize>,
+	replay_buddies: Query<Entity, With<ReplayBuddy>>,
+	stashed_buddies: Query<Entity, With<StashedBuddy>>,
+	children: Query<&Children>,
+	mut visibilities: Query<&mut Visibility, Without<SkinHidden>>,
+) {
+	for entity in replay_buddies.iter() {
+		commands.entity(entity).despawn_recursive();
+	}
+	for entity in stashed_buddies.iter() {
+		commands.entity(entity).remove::<StashedBuddy>().insert(Side::Left);
+		set_visibility_recursive(entity, true, &children, &mut visibilities);
+	}
+	// leaving halfway through can leave a result message up
+	for entity in battle_messages.entities() {
+		if let Ok(mut visibility) = visibilities.get_mut(entity) {
+			visibility.is_visible = false;
+		}
+	}
+	commands.entity(replay_state.controls).despawn_recursive();
+
+	battle.mode = BattleMode::Live;
+	speed.paused = false;
+	speed.step = false;
+	if team_size.0 != replay_state.team_size {
+		team_size.0 = replay_state.team_size;
+	}
+}
@@ -0,0 +1,66 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Every random game decision draws from here, so a run can be played again from its seed.
+/// Each round reseeds the shop and item rolls, which keeps them the same no matter how the
+/// earlier rounds went. Cosmetic randomness (blinking, wobbling) doesn't go through this.
+pub struct GameRng {
+	pub seed: u64,
+	round: usize,
+	shop: StdRng,
+	items: StdRng,
+}
+
+// keeps the streams apart when deriving their seeds
+const SHOP_STREAM: u64 = 1;
+const ITEM_STREAM: u64 = 2;
+const BATTLE_STREAM: u64 = 3;
+
+impl Default for GameRng {
+	fn default() -> Self {
+		Self::new(rand::thread_rng().gen())
+	}
+}
+
+impl GameRng {
+	pub fn new(seed: u64) -> Self {
+		Self {
+			seed,
+			round: 0,
+			shop: StdRng::seed_from_u64(derive_seed(seed, SHOP_STREAM, 0)),
+			items: StdRng::seed_from_u64(derive_seed(seed, ITEM_STREAM, 0)),
+		}
+	}
+
+	/// Rolls for the buddies offered in the shop on `round`, rerolls included
+	pub fn shop(&mut self, round: usize) -> &mut StdRng {
+		self.start_round(round);
+		&mut self.shop
+	}
+
+	/// Rolls for the items offered in the shop on `round`, rerolls included
+	pub fn items(&mut self, round: usize) -> &mut StdRng {
+		self.start_round(round);
+		&mut self.items
+	}
+
+	/// Seed for the opponent of the battle on `round`
+	pub fn battle_seed(&self, round: usize) -> u64 {
+		derive_seed(self.seed, BATTLE_STREAM, round)
+	}
+
+	fn start_round(&mut self, round: usize) {
+		if self.round != round {
+			self.round = round;
+			self.shop = StdRng::seed_from_u64(derive_seed(self.seed, SHOP_STREAM, round));
+			self.items = StdRng::seed_from_u64(derive_seed(self.seed, ITEM_STREAM, round));
+		}
+	}
+}
+
+// splitmix64, so neighbouring rounds and streams end up with unrelated seeds
+pub fn derive_seed(seed: u64, stream: u64, round: usize) -> u64 {
+	let mut z = seed ^ stream.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ ((round as u64) << 32);
+	z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+	z ^ (z >> 31)
+}
@@ -0,0 +1,268 @@
+use crate::{
+	game::{
+		balance::BalanceConfig,
+		buddy::{Buddy, Side},
+		counters::{Coins, RunProgress},
+		daily::{DailyChallenge, Day},
+		pause::{team_records, SavedTeam, TeamBuddy},
+		replay::BuddyRecord,
+		rng::GameRng,
+	},
+	network::Network,
+	types::{NetworkMessage, RunScore},
+	widget::{spawn_button, ButtonPressed},
+	AppState,
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct RunPlugin;
+
+impl Plugin for RunPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_startup_system(load_run)
+			// live battles only, replays don't change the run
+			.add_system_set(SystemSet::on_exit(AppState::Battle).with_system(save_finished_battle))
+			.add_system_set(
+				SystemSet::on_enter(AppState::RunSummary)
+					.with_system(spawn_run_summary)
+					.with_system(post_run_score),
+			)
+			.add_system_set(SystemSet::on_update(AppState::RunSummary).with_system(new_run_button))
+			.add_system_set(
+				SystemSet::on_exit(AppState::RunSummary).with_system(cleanup_run_summary),
+			);
+	}
+}
+
+struct RunSummaryData {
+	root: Entity,
+}
+
+#[derive(Component)]
+pub struct NewRunButton;
+
+/// A run ends once enough trophies are won or all lives are lost
+pub fn run_over(balance: &BalanceConfig, run: &RunProgress) -> bool {
+	run.won >= balance.run.trophy_target || run.lives == 0
+}
+
+/// There is a run to pick up again: it got past the first shop and isn't over yet
+pub fn can_continue(balance: &BalanceConfig, run: &RunProgress, saved_team: &SavedTeam) -> bool {
+	let started = run.rounds > 0 || run.spent > 0 || !saved_team.0.is_empty();
+	started && !run_over(balance, run)
+}
+
+/// Starts over with starting coins and lives, no trophies and no team. Regular runs get a fresh
+/// seed, the daily challenge for `day` gets the day's seed. The account's trophies are left alone.
+pub fn reset_run(
+	balance: &BalanceConfig,
+	coins: &mut Coins,
+	run: &mut RunProgress,
+	daily: &mut DailyChallenge,
+	game_rng: &mut GameRng,
+	saved_team: &mut SavedTeam,
+	day: Option<Day>,
+) {
+	*coins = Coins(balance.run.starting_coins);
+	*run = RunProgress::new(balance);
+	match day {
+		Some(day) => {
+			*game_rng = GameRng::new(day.seed());
+			*daily = DailyChallenge(Some(day.date));
+		},
+		None => {
+			*game_rng = GameRng::default();
+			*daily = DailyChallenge(None);
+		},
+	}
+	saved_team.0.clear();
+	SavedRun::new(run, Vec::new(), game_rng, daily).save();
+}
+
+// next to the executable's working directory, like the settings
+const RUN_PATH: &str = "run.json";
+
+/// Everything Continue needs to pick a run up again after a restart, kept in `run.json`
+#[derive(Serialize, Deserialize)]
+pub struct SavedRun {
+	#[serde(flatten)]
+	progress: RunProgress,
+	/// At base stats, like `SavedTeam`
+	#[serde(default)]
+	team: Vec<BuddyRecord>,
+	/// Of the `GameRng`, so the shops and opponents still to come don't change
+	#[serde(default)]
+	seed: Option<u64>,
+	/// The date of the daily challenge being played, if any
+	#[serde(default)]
+	daily: Option<String>,
+}
+
+impl SavedRun {
+	pub fn new(
+		progress: &RunProgress,
+		team: Vec<BuddyRecord>,
+		game_rng: &GameRng,
+		daily: &DailyChallenge,
+	) -> Self {
+		Self { progress: *progress, team, seed: Some(game_rng.seed), daily: daily.0.clone() }
+	}
+
+	/// The saved run, if there is one that can be read (there never is on wasm)
+	fn load() -> Option<Self> {
+		if cfg!(target_arch = "wasm32") {
+			return None;
+		}
+		let json = std::fs::read_to_string(RUN_PATH).ok()?;
+		serde_json::from_str(&json)
+			.map_err(|err| error!("failed to parse {}, starting a fresh run: {}", RUN_PATH, err))
+			.ok()
+	}
+
+	pub fn save(&self) {
+		if cfg!(target_arch = "wasm32") {
+			return;
+		}
+		let result = serde_json::to_string_pretty(self)
+			.map_err(|err| err.to_string())
+			.and_then(|json| std::fs::write(RUN_PATH, json).map_err(|err| err.to_string()));
+		if let Err(err) = result {
+			error!("failed to save {}: {}", RUN_PATH, err);
+		}
+	}
+}
+
+/// Saves the run with the team on the field, or the one put aside for the menu once it's gone
+pub fn save_run(
+	run: &RunProgress,
+	team: &Query<TeamBuddy, With<Buddy>>,
+	saved_team: &SavedTeam,
+	game_rng: &GameRng,
+	daily: &DailyChallenge,
+) {
+	let mut records = team_records(team.iter());
+	if records.is_empty() {
+		records = saved_team.0.clone();
+	}
+	SavedRun::new(run, records, game_rng, daily).save();
+}
+
+fn load_run(
+	mut run: ResMut<RunProgress>,
+	mut saved_team: ResMut<SavedTeam>,
+	mut game_rng: ResMut<GameRng>,
+	mut daily: ResMut<DailyChallenge>,
+) {
+	if let Some(saved) = SavedRun::load() {
+		*run = saved.progress;
+		saved_team.0 = saved.team;
+		if let Some(seed) = saved.seed {
+			*game_rng = GameRng::new(seed);
+		}
+		*daily = DailyChallenge(saved.daily);
+	}
+}
+
+fn save_finished_battle(
+	run: Res<RunProgress>,
+	team: Query<TeamBuddy, With<Buddy>>,
+	saved_team: Res<SavedTeam>,
+	game_rng: Res<GameRng>,
+	daily: Res<DailyChallenge>,
+) {
+	save_run(&run, &team, &saved_team, &game_rng, &daily);
+}
+
+fn spawn_run_summary(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	balance: Res<BalanceConfig>,
+	run: Res<RunProgress>,
+	daily: Res<DailyChallenge>,
+) {
+	let title = if run.won >= balance.run.trophy_target { "Victory!" } else { "Game over" };
+	let mut stats = vec![
+		format!("Trophies: {}/{}", run.won, balance.run.trophy_target),
+		format!("Rounds played: {}", run.rounds),
+		format!("Lives left: {}", run.lives),
+	];
+	if let Some(date) = &daily.0 {
+		stats.insert(0, format!("Daily challenge {}", date));
+	}
+	let text_style = |font_size| TextStyle {
+		font: asset_server.load("font/CaveatBrush-Regular.ttf"),
+		font_size,
+		color: Color::hex("323232").unwrap(),
+	};
+
+	let root = commands
+		.spawn_bundle(NodeBundle {
+			style: Style {
+				size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+				position_type: PositionType::Absolute,
+				// ui is laid out bottom to top, so the button comes first
+				flex_direction: FlexDirection::ColumnReverse,
+				justify_content: JustifyContent::Center,
+				align_items: AlignItems::Center,
+				..Default::default()
+			},
+			color: Color::rgba(1.0, 1.0, 1.0, 0.8).into(),
+			..Default::default()
+		})
+		.with_children(|parent| {
+			parent.spawn_bundle(TextBundle {
+				text: Text::with_section(title, text_style(120.0), Default::default()),
+				..Default::default()
+			});
+			for stat in stats {
+				parent.spawn_bundle(TextBundle {
+					text: Text::with_section(stat, text_style(50.0), Default::default()),
+					..Default::default()
+				});
+			}
+			spawn_button(parent, &asset_server, "New run", 150.0, NewRunButton);
+		})
+		.id();
+
+	commands.insert_resource(RunSummaryData { root });
+}
+
+fn post_run_score(run: Res<RunProgress>, mut network: ResMut<Network>) {
+	network.send_message(&NetworkMessage::RunScore(RunScore {
+		trophies: run.won as u32,
+		rounds: run.rounds as u32,
+		lives: run.lives as u32,
+	}));
+}
+
+fn new_run_button(
+	mut commands: Commands,
+	mut state: ResMut<State<AppState>>,
+	balance: Res<BalanceConfig>,
+	mut coins: ResMut<Coins>,
+	mut run: ResMut<RunProgress>,
+	mut daily: ResMut<DailyChallenge>,
+	mut game_rng: ResMut<GameRng>,
+	mut saved_team: ResMut<SavedTeam>,
+	mut pressed: EventReader<ButtonPressed>,
+	new_run_buttons: Query<(), With<NewRunButton>>,
+	buddies: Query<Entity, (With<Buddy>, With<Side>)>,
+) {
+	for ButtonPressed(button) in pressed.iter() {
+		if new_run_buttons.get(*button).is_err() {
+			continue;
+		}
+		// a new run is a regular one with a fresh seed, even after a daily challenge
+		reset_run(&balance, &mut coins, &mut run, &mut daily, &mut game_rng, &mut saved_team, None);
+		// start over with an empty team and a fresh shop, frozen offers included
+		for entity in buddies.iter() {
+			commands.entity(entity).despawn_recursive();
+		}
+		state.set(AppState::Shop).unwrap();
+	}
+}
+
+fn cleanup_run_summary(mut commands: Commands, run_summary: Res<RunSummaryData>) {
+	commands.entity(run_summary.root).despawn_recursive();
+}
@@ -0,0 +1,310 @@
+use crate::{
+	actions::Pointer,
+	game::{
+		balance::BalanceConfig,
+		buddy::{Buddy, Health, Level, Side, Slot, Strength, TeamSize},
+		counters::Coins,
+		shop::{on_buddy, BuddyDragState, Trash},
+		skin::SkinHidden,
+		ui::UiRoot,
+		Z_MESSAGE,
+	},
+	tween::{Animator, Ease, SpriteColorLens, TextColorLens, TranslationLens, Tween},
+	widget::{spawn_game_button, ButtonPressed},
+	AppState,
+};
+use bevy::{math::const_vec2, prelude::*, text::Text2dSize};
+use std::time::Duration;
+
+pub struct SellPlugin;
+
+impl Plugin for SellPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_event::<BuddySold>()
+			.init_resource::<SoldBuddies>()
+			.add_system_set(SystemSet::on_enter(AppState::Shop).with_system(enter_sell))
+			.add_system_set(
+				SystemSet::on_update(AppState::Shop)
+					.with_system(sell_buddy)
+					.with_system(update_sell_preview)
+					.with_system(undo_sell_button),
+			)
+			.add_system_set(SystemSet::on_pause(AppState::Shop).with_system(pause_sell))
+			.add_system_set(SystemSet::on_exit(AppState::Shop).with_system(exit_sell));
+	}
+}
+
+const SELL_PREVIEW_OFFSET: Vec2 = const_vec2!([0.0, 150.0]);
+const COIN_POPUP_DURATION: Duration = Duration::from_millis(1200);
+
+/// Sent when a team buddy is dropped on the trash
+pub struct BuddySold {
+	pub buddy: Entity,
+}
+
+/// Buddies sold during the current shop phase. They stay hidden until the shop is left so the
+/// last sale can be undone.
+#[derive(Default)]
+pub struct SoldBuddies(Vec<SoldBuddy>);
+
+struct SoldBuddy {
+	buddy: Entity,
+	slot: usize,
+	refund: usize,
+}
+
+pub struct SellState {
+	preview: Entity,
+	undo_button: Entity,
+}
+
+/// Half of what the buddy's stats would cost in the shop, plus a coin for every level past the
+/// first
+pub fn sell_value(
+	balance: &BalanceConfig,
+	level: &Level,
+	health: &Health,
+	strength: &Strength,
+) -> usize {
+	let price = balance.price(health.0.base() + strength.0.base());
+	(price / 2).max(1) + level.level - 1
+}
+
+pub fn enter_sell(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	ui_root: Query<Entity, With<UiRoot>>,
+) {
+	let preview = commands
+		.spawn_bundle(Text2dBundle {
+			text: Text::with_section(
+				"",
+				TextStyle {
+					font: asset_server.load("font/CaveatBrush-Regular.ttf"),
+					font_size: 70.0,
+					color: Color::hex("323232").unwrap(),
+				},
+				TextAlignment {
+					vertical: VerticalAlign::Center,
+					horizontal: HorizontalAlign::Center,
+				},
+			),
+			visibility: Visibility { is_visible: false },
+			..Default::default()
+		})
+		.id();
+	let undo_button = spawn_undo_button(&mut commands, &asset_server, ui_root.single());
+	commands.insert_resource(SellState { preview, undo_button });
+}
+
+pub fn exit_sell(
+	mut commands: Commands,
+	sell_state: Res<SellState>,
+	mut sold_buddies: ResMut<SoldBuddies>,
+) {
+	// sales are final once the shop closes
+	for sold in sold_buddies.0.drain(..) {
+		commands.entity(sold.buddy).despawn_recursive();
+	}
+	commands.entity(sell_state.preview).despawn_recursive();
+	commands.entity(sell_state.undo_button).despawn_recursive();
+}
+
+// undo_sell_button shows the button again once the shop resumes
+fn pause_sell(sell_state: Res<SellState>, mut buttons: Query<&mut Style, With<UndoSellButton>>) {
+	if let Ok(mut style) = buttons.get_mut(sell_state.undo_button) {
+		style.display = Display::None;
+	}
+}
+
+#[derive(Component)]
+pub struct UndoSellButton;
+
+fn spawn_undo_button(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	ui_root: Entity,
+) -> Entity {
+	let mut undo_button = None;
+	commands.entity(ui_root).with_children(|parent| {
+		let style = Style {
+			size: Size::new(Val::Px(150.0), Val::Px(65.0)),
+			position_type: PositionType::Absolute,
+			position: Rect { right: Val::Px(100.0), bottom: Val::Px(115.0), ..Default::default() },
+			display: Display::None,
+			..Default::default()
+		};
+		undo_button =
+			Some(spawn_game_button(parent, asset_server, "Undo sell", style, UndoSellButton));
+	});
+
+	undo_button.unwrap()
+}
+
+fn sell_buddy(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	balance: Res<BalanceConfig>,
+	mut coins: ResMut<Coins>,
+	mut sold_buddies: ResMut<SoldBuddies>,
+	mut events: EventReader<BuddySold>,
+	buddies: Query<(&Slot, &Level, &Health, &Strength)>,
+	trash: Query<&Transform, With<Trash>>,
+	children: Query<&Children>,
+	mut visibilities: Query<&mut Visibility, Without<SkinHidden>>,
+) {
+	for BuddySold { buddy } in events.iter() {
+		let (slot, level, health, strength) = buddies.get(*buddy).unwrap();
+		let refund = sell_value(&balance, level, health, strength);
+		coins.0 += refund;
+		sold_buddies.0.push(SoldBuddy { buddy: *buddy, slot: slot.base, refund });
+
+		// without a side the buddy drops out of the team until the sale is undone
+		commands.entity(*buddy).remove::<Side>();
+		set_visibility_recursive(*buddy, false, &children, &mut visibilities);
+		spawn_coin_popup(&mut commands, &asset_server, trash.single().translation, refund);
+	}
+}
+
+fn undo_sell_button(
+	mut commands: Commands,
+	sell_state: Res<SellState>,
+	mut coins: ResMut<Coins>,
+	team_size: Res<TeamSize>,
+	mut sold_buddies: ResMut<SoldBuddies>,
+	mut pressed: EventReader<ButtonPressed>,
+	mut buttons: Query<&mut Style, With<UndoSellButton>>,
+	team: Query<(&Side, &Slot), With<Buddy>>,
+	children: Query<&Children>,
+	mut visibilities: Query<&mut Visibility, Without<SkinHidden>>,
+) {
+	for ButtonPressed(button) in pressed.iter() {
+		if buttons.get(*button).is_err() {
+			continue;
+		}
+		let can_undo = matches!(sold_buddies.0.last(), Some(sold) if coins.0 >= sold.refund);
+		if !can_undo {
+			continue;
+		}
+		let sold = sold_buddies.0.pop().unwrap();
+		let occupied_slots = team
+			.iter()
+			.filter(|(side, _)| **side == Side::Left)
+			.map(|(_, slot)| slot.current)
+			.collect::<Vec<_>>();
+		let open_slot = if occupied_slots.contains(&sold.slot) {
+			(0..team_size.0).find(|i| !occupied_slots.contains(i))
+		} else {
+			Some(sold.slot)
+		};
+		if let Some(open_slot) = open_slot {
+			coins.0 -= sold.refund;
+			commands.entity(sold.buddy).insert(Side::Left).insert(Slot::new(open_slot));
+			set_visibility_recursive(sold.buddy, true, &children, &mut visibilities);
+		} else {
+			sold_buddies.0.push(sold);
+		}
+	}
+
+	if let Ok(mut style) = buttons.get_mut(sell_state.undo_button) {
+		let display = if sold_buddies.0.is_empty() { Display::None } else { Display::Flex };
+		if style.display != display {
+			style.display = display;
+		}
+	}
+}
+
+/// Shows or hides an entity and everything under it, parts a skin hides stay hidden
+pub fn set_visibility_recursive(
+	entity: Entity,
+	is_visible: bool,
+	children: &Query<&Children>,
+	visibilities: &mut Query<&mut Visibility, Without<SkinHidden>>,
+) {
+	if let Ok(mut visibility) = visibilities.get_mut(entity) {
+		visibility.is_visible = is_visible;
+	}
+	if let Ok(entity_children) = children.get(entity) {
+		for child in entity_children.iter().copied() {
+			set_visibility_recursive(child, is_visible, children, visibilities);
+		}
+	}
+}
+
+fn update_sell_preview(
+	sell_state: Res<SellState>,
+	balance: Res<BalanceConfig>,
+	buddy_drag_state: Res<BuddyDragState>,
+	pointer: Res<Pointer>,
+	buddies: Query<(&Side, &Level, &Health, &Strength), With<Buddy>>,
+	trash: Query<&Transform, With<Trash>>,
+	mut previews: Query<(&mut Text, &mut Visibility, &mut Transform), Without<Trash>>,
+) {
+	let (mut text, mut visibility, mut transform) = previews.get_mut(sell_state.preview).unwrap();
+	let trash = trash.single();
+	visibility.is_visible = false;
+
+	if let (BuddyDragState::Dragging { buddy, .. }, Some(cursor_world)) =
+		(&*buddy_drag_state, pointer.position)
+	{
+		if let Ok((Side::Left, level, health, strength)) = buddies.get(*buddy) {
+			if on_buddy(cursor_world, trash, pointer.reach) {
+				text.sections[0].value =
+					format!("+{}", sell_value(&balance, level, health, strength));
+				visibility.is_visible = true;
+				*transform = Transform::from_translation(
+					(trash.translation.truncate() + SELL_PREVIEW_OFFSET).extend(Z_MESSAGE),
+				);
+			}
+		}
+	}
+}
+
+fn spawn_coin_popup(commands: &mut Commands, asset_server: &AssetServer, at: Vec3, amount: usize) {
+	let start = Vec3::new(at.x, at.y + 60.0, Z_MESSAGE);
+	let text_color = Color::hex("323232").unwrap();
+	commands
+		.spawn_bundle(SpriteBundle {
+			texture: asset_server.load("money.png"),
+			transform: Transform::from_translation(start),
+			..Default::default()
+		})
+		.insert(
+			Animator::new(Tween::new(
+				COIN_POPUP_DURATION,
+				Ease::InOutCirc,
+				TranslationLens { start, end: start + Vec3::new(0.0, 80.0, 0.0) },
+			))
+			.despawn_when_done(),
+		)
+		.insert(Animator::new(Tween::new(
+			COIN_POPUP_DURATION,
+			Ease::InOutCirc,
+			SpriteColorLens { start: Color::WHITE, end: Color::rgba(1.0, 1.0, 1.0, 0.0) },
+		)))
+		.with_children(|parent| {
+			parent
+				.spawn_bundle(Text2dBundle {
+					text: Text::with_section(
+						format!("+{}", amount),
+						TextStyle {
+							font: asset_server.load("font/CaveatBrush-Regular.ttf"),
+							font_size: 60.0,
+							color: text_color,
+						},
+						TextAlignment {
+							vertical: VerticalAlign::Bottom,
+							horizontal: HorizontalAlign::Left,
+						},
+					),
+					text_2d_size: Text2dSize { size: Size::new(100., 100.) },
+					transform: Transform::from_xyz(40.0, -30.0, 0.1),
+					..Default::default()
+				})
+				.insert(Animator::new(Tween::new(
+					COIN_POPUP_DURATION,
+					Ease::InOutCirc,
+					TextColorLens { start: text_color, end: *text_color.clone().set_a(0.0) },
+				)));
+		});
+}
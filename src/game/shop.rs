@@ -1,12 +1,24 @@
 use crate::{
+	actions::{ActionPressed, GameAction, Pointer},
 	game::{
-		buddy::{Buddy, BuddyTemplate, Side, Slot},
-		counters::{set_coin_text, set_trophies_text, Coins, Trophies},
-		pad::{position_pad, spawn_pad},
+		balance::BalanceConfig,
+		buddy::{
+			merge_buddy, same_species, Buddy, BuddyColor, BuddyFace, BuddyTemplate, Health, Level,
+			Side, Slot, Strength, TeamSize,
+		},
+		counters::{set_coin_text, set_lives_text, set_trophies_text, Coins, RunProgress},
+		cursor::MovePointer,
+		drag::{drop_target, DropTarget, SwapHop},
+		item::{spawn_shop_items, ShopItem},
+		pad::{pad_center_offset, position_pad, spawn_pad, sync_team_pads, Pad},
+		rng::GameRng,
+		sell::BuddySold,
 		ui::UiRoot,
 		Z_BUDDY,
 	},
 	network::ShouldSaveGame,
+	view::View,
+	widget::{spawn_game_button, spawn_image_button, ButtonPressed},
 	AppState,
 };
 use bevy::{
@@ -16,31 +28,44 @@ use bevy::{
 	text::Text2dSize,
 	ui::FocusPolicy,
 };
+use rand::Rng;
 
 pub struct ShopPlugin;
 
 impl Plugin for ShopPlugin {
 	fn build(&self, app: &mut App) {
-		app.insert_resource(Coins(6))
-			.insert_resource(Trophies { won: 0, rounds: 0 })
+		app.init_resource::<RunProgress>()
 			.insert_resource(BuddyDragState::None)
+			.add_event::<ShopEvent>()
 			.add_system_set(SystemSet::on_enter(AppState::Startup).with_system(spawn_shop_base))
 			.add_system_set(SystemSet::on_enter(AppState::Shop).with_system(enter_shop))
 			.add_system_set(
 				SystemSet::on_update(AppState::Shop)
 					.with_system(set_coin_text)
 					.with_system(set_trophies_text)
+					.with_system(set_lives_text)
 					.with_system(position_pad)
-					.with_system(buy_buddy)
+					.with_system(sync_team_pads)
+					.with_system(buy_buddy.after(MovePointer))
 					.with_system(update_price_counter)
 					.with_system(battle_button)
+					.with_system(fit_shop_controls)
+					.with_system(reroll_button)
+					.with_system(toggle_freeze.after(MovePointer))
+					.with_system(update_freeze_sprites)
 					.with_system(save_button),
 			)
+			.add_system_set(SystemSet::on_pause(AppState::Shop).with_system(pause_shop))
+			.add_system_set(SystemSet::on_resume(AppState::Shop).with_system(resume_shop))
 			.add_system_set(SystemSet::on_exit(AppState::Shop).with_system(exit_shop));
 	}
 }
 
-const SHOP_BUDDY_SLOTS: usize = 3;
+pub const SHOP_BUDDY_SLOTS: usize = 3;
+// how far the trash sits left of the team when it is centered
+const TRASH_OFFSET: f32 = 270.0;
+const FREEZE_TOGGLE_POSITION: Vec2 = const_vec2!([0.0, 88.0]);
+const FREEZE_TOGGLE_RADIUS: f32 = 20.0;
 
 #[derive(Component)]
 pub struct ShopPad;
@@ -58,46 +83,92 @@ pub struct ShopState {
 	battle_button: Entity,
 	trash: Entity,
 	save_button: Entity,
+	reroll_button: Entity,
 }
 
 pub fn enter_shop(
 	mut commands: Commands,
 	asset_server: Res<AssetServer>,
-	trophies: Res<Trophies>,
+	balance: Res<BalanceConfig>,
+	run: Res<RunProgress>,
+	mut game_rng: ResMut<GameRng>,
+	mut team_size: ResMut<TeamSize>,
 	ui_root: Query<Entity, With<UiRoot>>,
-	buddies: Query<(Entity, &Side), With<Buddy>>,
+	buddies: Query<(Entity, &Side, &Slot, Option<&Frozen>), With<Buddy>>,
 ) {
+	let size = balance.team_size(run.rounds);
+	if team_size.0 != size {
+		team_size.0 = size;
+	}
+
 	let ui_root = ui_root.single();
 	let battle_button = spawn_battle_button(&mut commands, &asset_server, ui_root);
 	let save_button = spawn_save_button(&mut commands, &asset_server, ui_root);
+	let reroll_button =
+		spawn_reroll_button(&mut commands, &asset_server, ui_root, balance.reroll_cost);
 	let trash = commands
 		.spawn_bundle(SpriteBundle {
 			texture: asset_server.load("trash.png"),
-			transform: Transform::from_xyz(-450.0, 40.0, Z_BUDDY - 1.0)
-				.with_scale(Vec3::new(0.8, 0.8, 1.0)),
+			transform: Transform::from_xyz(
+				-(pad_center_offset(size) + TRASH_OFFSET),
+				40.0,
+				Z_BUDDY - 1.0,
+			)
+			.with_scale(Vec3::new(0.8, 0.8, 1.0)),
 			..Default::default()
 		})
 		.insert(Trash)
 		.id();
-	commands.insert_resource(ShopState { battle_button, trash, save_button });
+	commands.insert_resource(ShopState { battle_button, trash, save_button, reroll_button });
 
-	// clean up old shop entities
-	for (entity, side) in buddies.iter() {
+	// clean up old shop entities, frozen ones stick around for this round
+	let mut frozen_slots = Vec::new();
+	for (entity, side, slot, frozen) in buddies.iter() {
 		if *side == Side::Shop {
-			commands.entity(entity).despawn_recursive();
+			if frozen.is_some() {
+				frozen_slots.push(slot.current);
+			} else {
+				commands.entity(entity).despawn_recursive();
+			}
 		}
 	}
 
+	spawn_shop_buddies(
+		&mut commands,
+		&asset_server,
+		&balance,
+		game_rng.shop(run.rounds),
+		run.rounds,
+		&frozen_slots,
+	);
+}
+
+/// Fills every shop slot that isn't in `occupied_slots` with a new offer
+fn spawn_shop_buddies(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	balance: &BalanceConfig,
+	rng: &mut impl Rng,
+	rounds: usize,
+	occupied_slots: &[usize],
+) {
 	for i in 0..SHOP_BUDDY_SLOTS {
-		let template = BuddyTemplate::random_for_round(trophies.rounds + 2);
+		// every slot is rolled so freezing doesn't shift the offers that follow
+		let tier = balance.roll_tier(rng, rounds);
+		let template = BuddyTemplate::random_for_tier(rng, tier);
+		if occupied_slots.contains(&i) {
+			continue;
+		}
+		let price = balance.price(template.base_cost());
 		let buddy_id = template.spawn(
-			&mut commands,
-			&asset_server,
+			commands,
+			asset_server,
 			i,
 			Side::Shop,
 			Transform::from_xyz(0.0, -500.0, 0.0),
 		);
-		add_price(&mut commands, &asset_server, buddy_id, 2);
+		add_price(commands, asset_server, buddy_id, price, BUDDY_PRICE_POSITION);
+		add_freeze_toggle(commands, asset_server, buddy_id);
 	}
 }
 
@@ -120,6 +191,41 @@ pub fn exit_shop(
 	commands.entity(shop_state.battle_button).despawn_recursive();
 	commands.entity(shop_state.trash).despawn_recursive();
 	commands.entity(shop_state.save_button).despawn_recursive();
+	commands.entity(shop_state.reroll_button).despawn_recursive();
+}
+
+/// Hides the shop controls while another state (like a replay) plays on top of the shop
+pub fn pause_shop(
+	shop_state: Res<ShopState>,
+	mut styles: Query<&mut Style>,
+	mut visibilities: Query<&mut Visibility>,
+) {
+	show_shop_controls(false, &shop_state, &mut styles, &mut visibilities);
+}
+
+pub fn resume_shop(
+	shop_state: Res<ShopState>,
+	mut styles: Query<&mut Style>,
+	mut visibilities: Query<&mut Visibility>,
+) {
+	show_shop_controls(true, &shop_state, &mut styles, &mut visibilities);
+}
+
+fn show_shop_controls(
+	shown: bool,
+	shop_state: &ShopState,
+	styles: &mut Query<&mut Style>,
+	visibilities: &mut Query<&mut Visibility>,
+) {
+	let display = if shown { Display::Flex } else { Display::None };
+	for button in [shop_state.battle_button, shop_state.save_button, shop_state.reroll_button] {
+		if let Ok(mut style) = styles.get_mut(button) {
+			style.display = display;
+		}
+	}
+	if let Ok(mut visibility) = visibilities.get_mut(shop_state.trash) {
+		visibility.is_visible = shown;
+	}
 }
 
 #[derive(Component)]
@@ -145,167 +251,281 @@ fn spawn_battle_button(
 			})
 			.insert(FocusPolicy::Pass)
 			.with_children(|parent| {
-				battle_button = Some(
-					parent
-						.spawn_bundle(ButtonBundle {
-							style: Style {
-								size: Size::new(Val::Px(236.0), Val::Px(186.0)),
-								margin: Rect {
-									bottom: Val::Auto,
-									top: Val::Auto,
-									right: Val::Px(100.0),
-									..Default::default()
-								},
-								// horizontally center child text
-								justify_content: JustifyContent::Center,
-								// vertically center child text
-								align_items: AlignItems::Center,
-								align_self: AlignSelf::FlexEnd,
-								..Default::default()
-							},
-							color: Color::NONE.into(),
-							..Default::default()
-						})
-						.insert(BattleButton)
-						.with_children(|parent| {
-							parent
-								.spawn_bundle(ImageBundle {
-									image: asset_server.load("battle_button.png").into(),
-									..Default::default()
-								})
-								.insert(FocusPolicy::Pass);
-						})
-						.id(),
-				)
+				let style = Style {
+					size: Size::new(Val::Px(236.0), Val::Px(186.0)),
+					margin: Rect {
+						bottom: Val::Auto,
+						top: Val::Auto,
+						right: Val::Px(100.0),
+						..Default::default()
+					},
+					align_self: AlignSelf::FlexEnd,
+					..Default::default()
+				};
+				battle_button = Some(spawn_image_button(
+					parent,
+					asset_server,
+					"battle_button.png",
+					style,
+					BattleButton,
+				));
 			});
 	});
 
 	battle_button.unwrap()
 }
 
+/// Shrinks the battle button and tucks the buttons into the corner on portrait screens like
+/// phones, where the team takes up the whole width
+fn fit_shop_controls(
+	view: Res<View>,
+	mut battle_buttons: Query<&mut Style, With<BattleButton>>,
+	mut reroll_buttons: Query<&mut Style, (With<RerollButton>, Without<BattleButton>)>,
+) {
+	let portrait = view.is_portrait();
+	let (battle_size, battle_margin, corner_margin) = if portrait {
+		(
+			Size::new(Val::Px(118.0), Val::Px(93.0)),
+			Rect {
+				top: Val::Auto,
+				bottom: Val::Px(120.0),
+				right: Val::Px(20.0),
+				..Default::default()
+			},
+			Val::Px(20.0),
+		)
+	} else {
+		(
+			Size::new(Val::Px(236.0), Val::Px(186.0)),
+			Rect { bottom: Val::Auto, top: Val::Auto, right: Val::Px(100.0), ..Default::default() },
+			Val::Px(100.0),
+		)
+	};
+	// only touched when it differs, changing a style relayouts the ui
+	for mut style in battle_buttons.iter_mut() {
+		if style.size != battle_size || style.margin != battle_margin {
+			style.size = battle_size;
+			style.margin = battle_margin;
+		}
+	}
+	for mut style in reroll_buttons.iter_mut() {
+		if style.position.right != corner_margin {
+			style.position.right = corner_margin;
+		}
+	}
+}
+
 pub fn battle_button(
 	mut state: ResMut<State<AppState>>,
-	interaction_query: Query<&Interaction, (Changed<Interaction>, With<BattleButton>)>,
+	mut actions: EventReader<ActionPressed>,
+	mut pressed: EventReader<ButtonPressed>,
+	battle_buttons: Query<(), With<BattleButton>>,
 ) {
-	for interaction in interaction_query.iter() {
-		if *interaction == Interaction::Clicked {
-			state.set(AppState::Battle).unwrap();
-		}
+	let clicked = pressed.iter().any(|ButtonPressed(button)| battle_buttons.get(*button).is_ok());
+	if clicked || actions.iter().any(|ActionPressed(action)| *action == GameAction::Battle) {
+		state.set(AppState::Battle).unwrap();
 	}
 }
 
 const BUDDY_EXTENTS: Vec2 = const_vec2!([65.0, 65.0]);
+const BUDDY_PRICE_POSITION: Vec2 = const_vec2!([-90.0, 70.0]);
+// pads are PAD_SPACING apart, this keeps neighbouring buddies and items from both being in reach
+pub const MAX_REACH: f32 = 24.0;
+// releasing a shop buddy closer than this to where it was picked up counts as a click
+const CLICK_DISTANCE: f32 = 10.0;
+
+/// Something the player did in the shop, for feedback like sounds
+pub enum ShopEvent {
+	/// A buddy or item was paid for
+	Bought,
+	/// A team buddy was dropped into another slot
+	Placed,
+	/// Something was dropped where it could go but couldn't be paid for
+	Denied(Entity),
+}
 
 pub enum BuddyDragState {
-	Dragging { buddy: Entity, offset: Vec2 },
+	Dragging { buddy: Entity, offset: Vec2, start: Vec2 },
+	DraggingItem { item: Entity, offset: Vec2 },
 	None,
 }
 
 fn buy_buddy(
 	mut commands: Commands,
 	mut coins: ResMut<Coins>,
+	mut run: ResMut<RunProgress>,
+	team_size: Res<TeamSize>,
 	mut buddy_drag_state: ResMut<BuddyDragState>,
-	mouse_button: Res<Input<MouseButton>>,
-	windows: Res<Windows>,
-	cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+	mut sold_events: EventWriter<BuddySold>,
+	mut shop_events: EventWriter<ShopEvent>,
+	pointer: Res<Pointer>,
 	mut buddies: Query<(Entity, &Transform, &mut Slot, &mut Side, Option<&Price>), With<Buddy>>,
+	species: Query<(&BuddyFace, &BuddyColor)>,
+	mut stats: Query<(&mut Health, &mut Strength, &mut Level)>,
 	trash: Query<&Transform, (With<Trash>, Without<Buddy>)>,
+	pads: Query<(&Transform, &Side, &Slot), (With<Pad>, Without<Buddy>)>,
 	children: Query<&Children>,
 	price_counters: Query<&PriceCounter>,
 	price_icons: Query<&PriceIcon>,
 ) {
-	let window = windows.get_primary().unwrap();
-	let (camera, global_transform) = cameras.single();
-	let cursor_screen = if let Some(cursor) = window.cursor_position() {
+	let cursor_world = if let Some(cursor) = pointer.position {
 		cursor
 	} else {
 		return;
 	};
 
-	let cursor_world = screen_to_world(
-		Vec2::new(window.width(), window.height()),
-		cursor_screen,
-		camera,
-		global_transform,
-	);
-
 	if let BuddyDragState::Dragging { offset, .. } = &mut *buddy_drag_state {
 		*offset = cursor_world;
 	}
-	if mouse_button.just_pressed(MouseButton::Left) {
-		let occupied_slots = buddies
-			.iter()
-			.filter_map(
-				|(_, _, slot, side, _)| {
-					if *side == Side::Left {
-						Some(slot.current)
-					} else {
-						None
-					}
-				},
-			)
-			.collect::<Vec<_>>();
-		for (entity, transform, mut slot, mut side, price) in buddies.iter_mut() {
-			if on_buddy(cursor_world, transform) {
-				match *side {
-					Side::Left => {
-						*buddy_drag_state =
-							BuddyDragState::Dragging { buddy: entity, offset: cursor_world }
-					},
-					Side::Shop => {
-						if coins.0 >= price.unwrap().0 {
-							let open_slot = (0..3).find(|i| !occupied_slots.contains(i));
-							if let Some(open_slot) = open_slot {
-								*side = Side::Left;
-								*slot = Slot::new(open_slot);
-								coins.0 -= price.unwrap().0;
-								remove_price(
-									&mut commands,
-									entity,
-									&children,
-									&price_counters,
-									&price_icons,
-								)
+	if pointer.just_pressed {
+		if let BuddyDragState::None = &*buddy_drag_state {
+			for (entity, transform, _, side, _) in buddies.iter() {
+				if on_buddy(cursor_world, transform, pointer.reach) {
+					match *side {
+						Side::Left | Side::Shop => {
+							*buddy_drag_state = BuddyDragState::Dragging {
+								buddy: entity,
+								offset: cursor_world,
+								start: cursor_world,
 							}
-						}
-					},
-					Side::Right => error!("how did this even happen"),
+						},
+						Side::Right => error!("how did this even happen"),
+					}
+					break;
 				}
-				break;
 			}
 		}
 	}
-	if mouse_button.just_released(MouseButton::Left) {
-		if let BuddyDragState::Dragging { buddy, .. } = &*buddy_drag_state {
-			let old_buddy_slot = buddies.get_component::<Slot>(*buddy).unwrap().current;
-			let mut new_buddy_slot = None;
-			for (current, transform, mut slot, side, _) in buddies.iter_mut() {
-				if on_buddy(cursor_world, transform) && current != *buddy && *side == Side::Left {
-					new_buddy_slot = Some(slot.base);
-					*slot = Slot::new(old_buddy_slot);
-					break;
+	if pointer.just_released {
+		if let BuddyDragState::Dragging { buddy, start, .. } = &*buddy_drag_state {
+			let buddy = *buddy;
+			let is_shop_buddy = *buddies.get_component::<Side>(buddy).unwrap() == Side::Shop;
+			let target = drop_target(
+				cursor_world,
+				pointer.reach,
+				buddy,
+				team_size.0,
+				buddies
+					.iter()
+					.map(|(entity, transform, slot, side, _)| (entity, transform, side, slot)),
+				pads.iter(),
+				trash.get_single().ok(),
+			);
+			if is_shop_buddy {
+				let price = buddies.get_component::<Price>(buddy).unwrap().0;
+				let merge_target = match target {
+					DropTarget::Buddy(target) => Some(target).filter(|target| {
+						let target_level = stats.get_component::<Level>(*target).unwrap();
+						same_species(species.get(buddy).unwrap(), species.get(*target).unwrap())
+							&& !target_level.is_max()
+					}),
+					_ => None,
+				};
+				// a click buys into the first open slot, a drop into the slot it was dropped on
+				let buy_slot = match target {
+					DropTarget::Slot(slot) => Some(slot),
+					_ if cursor_world.distance(*start) < CLICK_DISTANCE + pointer.reach => {
+						let occupied_slots =
+							buddies
+								.iter()
+								.filter_map(|(_, _, slot, side, _)| {
+									if *side == Side::Left {
+										Some(slot.current)
+									} else {
+										None
+									}
+								})
+								.collect::<Vec<_>>();
+						(0..team_size.0).find(|i| !occupied_slots.contains(i))
+					},
+					_ => None,
+				};
+
+				// anything else (or not being able to afford it) snaps it back into the shop
+				if (merge_target.is_some() || buy_slot.is_some()) && coins.0 < price {
+					shop_events.send(ShopEvent::Denied(buddy));
+				} else if let Some(target) = merge_target {
+					let [(shop_health, shop_strength, _), (mut health, mut strength, mut level)] =
+						stats.many_mut([buddy, target]);
+					let (other_health, other_strength) =
+						(shop_health.0.base(), shop_strength.0.base());
+					merge_buddy(
+						&mut health,
+						&mut strength,
+						&mut level,
+						other_health,
+						other_strength,
+					);
+					coins.0 -= price;
+					run.spent += price;
+					commands.entity(buddy).despawn_recursive();
+					shop_events.send(ShopEvent::Bought);
+				} else if let Some(buy_slot) = buy_slot {
+					let (_, _, mut slot, mut side, _) = buddies.get_mut(buddy).unwrap();
+					*side = Side::Left;
+					*slot = Slot::new(buy_slot);
+					coins.0 -= price;
+					run.spent += price;
+					commands.entity(buddy).remove::<Frozen>();
+					shop_events.send(ShopEvent::Bought);
+					remove_price(&mut commands, buddy, &children, &price_counters, &price_icons)
+				}
+			} else {
+				match target {
+					DropTarget::Buddy(target) => {
+						let old_buddy_slot = buddies.get_component::<Slot>(buddy).unwrap().current;
+						let (_, target_transform, mut target_slot, ..) =
+							buddies.get_mut(target).unwrap();
+						let new_buddy_slot = target_slot.base;
+						*target_slot = Slot::new(old_buddy_slot);
+						// the displaced buddy hops over instead of sliding under the dropped one
+						commands.entity(target).insert(SwapHop::new(target_transform.translation));
+						*buddies.get_component_mut::<Slot>(buddy).unwrap() =
+							Slot::new(new_buddy_slot);
+						shop_events.send(ShopEvent::Placed);
+					},
+					DropTarget::Slot(new_buddy_slot) => {
+						*buddies.get_component_mut::<Slot>(buddy).unwrap() =
+							Slot::new(new_buddy_slot);
+						shop_events.send(ShopEvent::Placed);
+					},
+					DropTarget::Trash => sold_events.send(BuddySold { buddy }),
+					DropTarget::None => {},
 				}
 			}
-
-			if let Some(new_buddy_slot) = new_buddy_slot {
-				let mut slot = buddies.get_component_mut::<Slot>(*buddy).unwrap();
-				*slot = Slot::new(new_buddy_slot);
-			} else if on_buddy(cursor_world, trash.single()) {
-				commands.entity(*buddy).despawn_recursive();
-			}
+			*buddy_drag_state = BuddyDragState::None;
 		}
-		*buddy_drag_state = BuddyDragState::None;
 	}
 }
 
-fn on_buddy(position: Vec2, buddy_transform: &Transform) -> bool {
+/// Whether `position` is on the buddy, or within `reach` of its edges
+pub fn on_buddy(position: Vec2, buddy_transform: &Transform, reach: f32) -> bool {
 	let pos = buddy_transform.translation;
-	let min = pos.xy() - BUDDY_EXTENTS;
-	let max = pos.xy() + BUDDY_EXTENTS;
+	let extents = BUDDY_EXTENTS + Vec2::splat(reach.min(MAX_REACH));
+	let min = pos.xy() - extents;
+	let max = pos.xy() + extents;
 	position.x < max.x && position.x > min.x && position.y < max.y && position.y > min.y
 }
 
-fn screen_to_world(
+/// Where the mouse is in the world, if it's in the view rather than on the letterbox
+pub fn cursor_world_position(
+	windows: &Windows,
+	view: &View,
+	cameras: &Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+) -> Option<Vec2> {
+	let window = windows.get_primary().unwrap();
+	let (camera, global_transform) = cameras.single();
+	let cursor_screen = window.cursor_position().filter(|cursor| view.contains(*cursor))?;
+
+	Some(screen_to_world(
+		Vec2::new(window.width(), window.height()),
+		cursor_screen,
+		camera,
+		global_transform,
+	))
+}
+
+pub fn screen_to_world(
 	window_size: Vec2,
 	screen_pos: Vec2,
 	camera: &Camera,
@@ -317,8 +537,20 @@ fn screen_to_world(
 	world_pos.truncate()
 }
 
+/// The inverse of `screen_to_world`, in logical pixels from the bottom left of the window
+pub fn world_to_screen(
+	window_size: Vec2,
+	world_pos: Vec2,
+	camera: &Camera,
+	camera_transform: &GlobalTransform,
+) -> Vec2 {
+	let world_to_ndc = camera.projection_matrix * camera_transform.compute_matrix().inverse();
+	let ndc = world_to_ndc.project_point3(world_pos.extend(0.0)).truncate();
+	(ndc + Vec2::ONE) / 2.0 * window_size
+}
+
 #[derive(Component)]
-pub struct Price(usize);
+pub struct Price(pub usize);
 
 #[derive(Component)]
 pub struct PriceCounter;
@@ -331,11 +563,12 @@ pub fn add_price(
 	asset_server: &AssetServer,
 	entity: Entity,
 	price: usize,
+	position: Vec2,
 ) {
 	commands.entity(entity).insert(Price(price)).with_children(|parent| {
 		parent
 			.spawn_bundle(SpriteBundle {
-				transform: Transform::from_xyz(-90.0, 70.0, Z_BUDDY + 0.3)
+				transform: Transform::from_translation(position.extend(Z_BUDDY + 0.3))
 					.with_scale(Vec3::splat(0.5)),
 				texture: asset_server.load("price.png"),
 				..Default::default()
@@ -357,7 +590,9 @@ pub fn add_price(
 					},
 				),
 				text_2d_size: Text2dSize { size: Size::new(100., 100.) },
-				transform: Transform::from_xyz(-70.0, 38.0, Z_BUDDY + 0.1),
+				transform: Transform::from_translation(
+					(position + Vec2::new(20.0, -32.0)).extend(Z_BUDDY + 0.1),
+				),
 				..Default::default()
 			})
 			.insert(PriceCounter);
@@ -395,6 +630,157 @@ fn update_price_counter(
 	}
 }
 
+#[derive(Component)]
+pub struct RerollButton;
+
+fn spawn_reroll_button(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	ui_root: Entity,
+	cost: usize,
+) -> Entity {
+	let mut reroll_button = None;
+	commands.entity(ui_root).with_children(|parent| {
+		let style = Style {
+			size: Size::new(Val::Px(150.0), Val::Px(65.0)),
+			position_type: PositionType::Absolute,
+			position: Rect { right: Val::Px(100.0), bottom: Val::Px(40.0), ..Default::default() },
+			..Default::default()
+		};
+		let label = format!("Reroll ({})", cost);
+		reroll_button = Some(spawn_game_button(parent, asset_server, label, style, RerollButton));
+	});
+
+	reroll_button.unwrap()
+}
+
+/// Replaces every shop offer that isn't frozen, including items
+pub fn reroll_button(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	mut coins: ResMut<Coins>,
+	balance: Res<BalanceConfig>,
+	mut run: ResMut<RunProgress>,
+	mut game_rng: ResMut<GameRng>,
+	mut actions: EventReader<ActionPressed>,
+	mut pressed: EventReader<ButtonPressed>,
+	reroll_buttons: Query<(), With<RerollButton>>,
+	buddies: Query<(Entity, &Side, &Slot, Option<&Frozen>), With<Buddy>>,
+	items: Query<Entity, With<ShopItem>>,
+) {
+	let mut reroll = actions.iter().any(|ActionPressed(action)| *action == GameAction::Reroll);
+	reroll |= pressed.iter().any(|ButtonPressed(button)| reroll_buttons.get(*button).is_ok());
+	if !reroll || coins.0 < balance.reroll_cost {
+		return;
+	}
+	coins.0 -= balance.reroll_cost;
+	run.spent += balance.reroll_cost;
+
+	let mut frozen_slots = Vec::new();
+	for (entity, side, slot, frozen) in buddies.iter() {
+		if *side == Side::Shop {
+			if frozen.is_some() {
+				frozen_slots.push(slot.current);
+			} else {
+				commands.entity(entity).despawn_recursive();
+			}
+		}
+	}
+	spawn_shop_buddies(
+		&mut commands,
+		&asset_server,
+		&balance,
+		game_rng.shop(run.rounds),
+		run.rounds,
+		&frozen_slots,
+	);
+
+	for entity in items.iter() {
+		commands.entity(entity).despawn_recursive();
+	}
+	spawn_shop_items(&mut commands, &asset_server, &balance, game_rng.items(run.rounds));
+}
+
+/// A frozen shop buddy is kept for the next round and survives rerolls
+#[derive(Component)]
+pub struct Frozen;
+
+#[derive(Component)]
+pub struct FreezeToggle;
+
+#[derive(Component)]
+pub struct FrozenOverlay;
+
+pub fn add_freeze_toggle(commands: &mut Commands, asset_server: &AssetServer, entity: Entity) {
+	commands.entity(entity).with_children(|parent| {
+		parent
+			.spawn_bundle(SpriteBundle {
+				transform: Transform::from_translation(
+					FREEZE_TOGGLE_POSITION.extend(Z_BUDDY + 0.3),
+				)
+				.with_scale(Vec3::splat(0.5)),
+				texture: asset_server.load("freeze.png"),
+				..Default::default()
+			})
+			.insert(FreezeToggle);
+
+		parent
+			.spawn_bundle(SpriteBundle {
+				transform: Transform::from_xyz(0.0, 0.0, Z_BUDDY + 0.25)
+					.with_scale(Vec3::splat(0.5)),
+				texture: asset_server.load("frozen.png"),
+				visibility: Visibility { is_visible: false },
+				..Default::default()
+			})
+			.insert(FrozenOverlay);
+	});
+}
+
+fn toggle_freeze(
+	mut commands: Commands,
+	pointer: Res<Pointer>,
+	buddies: Query<(Entity, &Transform, &Side, Option<&Frozen>), With<Buddy>>,
+) {
+	if !pointer.just_pressed {
+		return;
+	}
+	let cursor_world = if let Some(cursor) = pointer.position {
+		cursor
+	} else {
+		return;
+	};
+
+	for (entity, transform, side, frozen) in buddies.iter() {
+		let toggle_position = transform.translation.xy() + FREEZE_TOGGLE_POSITION;
+		if *side == Side::Shop && cursor_world.distance(toggle_position) < FREEZE_TOGGLE_RADIUS {
+			if frozen.is_some() {
+				commands.entity(entity).remove::<Frozen>();
+			} else {
+				commands.entity(entity).insert(Frozen);
+			}
+			break;
+		}
+	}
+}
+
+fn update_freeze_sprites(
+	buddies: Query<(&Side, Option<&Frozen>), With<Buddy>>,
+	mut toggles: Query<(&mut Visibility, &mut Sprite, &Parent), With<FreezeToggle>>,
+	mut overlays: Query<(&mut Visibility, &Parent), (With<FrozenOverlay>, Without<FreezeToggle>)>,
+) {
+	for (mut visibility, mut sprite, parent) in toggles.iter_mut() {
+		if let Ok((side, frozen)) = buddies.get(parent.0) {
+			visibility.is_visible = *side == Side::Shop;
+			sprite.color.set_a(if frozen.is_some() { 1.0 } else { 0.5 });
+		}
+	}
+	for (mut visibility, parent) in overlays.iter_mut() {
+		if let Ok((side, frozen)) = buddies.get(parent.0) {
+			visibility.is_visible = *side == Side::Shop && frozen.is_some();
+		}
+	}
+}
+
 #[derive(Component)]
 pub struct SaveButton;
 
@@ -403,42 +789,30 @@ fn spawn_save_button(
 	asset_server: &AssetServer,
 	ui_root: Entity,
 ) -> Entity {
-	commands
-		.spawn_bundle(ButtonBundle {
-			style: Style {
-				size: Size::new(Val::Px(150.0), Val::Px(65.0)),
-				// center button
-				margin: Rect::all(Val::Auto),
-				// horizontally center child text
-				justify_content: JustifyContent::Center,
-				// vertically center child text
-				align_items: AlignItems::Center,
-				position: Rect { top: Val::Px(-250.0), ..default() },
-				..default()
-			},
-			color: UiColor(Color::rgba(0f32, 0f32, 0f32, 0f32)),
+	let mut save_button = None;
+	commands.entity(ui_root).with_children(|parent| {
+		let style = Style {
+			size: Size::new(Val::Px(150.0), Val::Px(65.0)),
+			// anchored to the top center of the view
+			position_type: PositionType::Absolute,
+			position: Rect { top: Val::Percent(12.0), left: Val::Percent(50.0), ..default() },
+			margin: Rect { left: Val::Px(-75.0), ..default() },
 			..default()
-		})
-		.insert(SaveButton)
-		.with_children(|parent| {
-			parent
-				.spawn_bundle(ImageBundle {
-					image: asset_server.load("save_button.png").into(),
-					..default()
-				})
-				.insert(FocusPolicy::Pass);
-		})
-		.id()
+		};
+		save_button =
+			Some(spawn_image_button(parent, asset_server, "save_button.png", style, SaveButton));
+	});
+
+	save_button.unwrap()
 }
 
 pub fn save_button(
 	mut state: ResMut<State<AppState>>,
 	mut save_game: ResMut<ShouldSaveGame>,
-	interaction_query: Query<&Interaction, (Changed<Interaction>, With<SaveButton>)>,
+	mut pressed: EventReader<ButtonPressed>,
+	save_buttons: Query<(), With<SaveButton>>,
 ) {
-	for interaction in interaction_query.iter() {
-		if *interaction == Interaction::Clicked {
-			save_game.0 = true;
-		}
+	if pressed.iter().any(|ButtonPressed(button)| save_buttons.get(*button).is_ok()) {
+		save_game.0 = true;
 	}
 }
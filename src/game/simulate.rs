@@ -0,0 +1,248 @@
+//! Headless balance simulation. Plays whole runs using the game's own `fight` rules, without a
+//! window, renderer or ECS, and reports how they went. The player shops like an opponent would.
+//!
+//! `blockchain_buddy simulate [--runs N] [--seed N] [--difficulty easy|normal|hard]
+//! [--player easy|normal|hard] [--format csv|json] [--out PATH]`
+
+use crate::game::{
+	balance::BalanceConfig,
+	battle::{fight, pay_out, Fight},
+	counters::{Coins, RunProgress},
+	opponent::{build_opponent, records, visit_shop, Difficulty},
+	replay::BattleOutcome,
+	run::run_over,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+
+// runs that keep tying could otherwise go on forever
+const MAX_ROUNDS: usize = 100;
+
+pub struct SimulationConfig {
+	pub runs: usize,
+	pub seed: u64,
+	/// How the opponents are built
+	pub difficulty: Difficulty,
+	/// How the player shops
+	pub player: Difficulty,
+}
+
+/// Totals for every battle fought in a given round across all runs
+#[derive(Default, Clone)]
+struct RoundStats {
+	pub round: usize,
+	pub battles: usize,
+	pub wins: usize,
+	pub losses: usize,
+	pub ties: usize,
+	/// Coins in hand when the shop opened
+	pub coins: usize,
+	pub team_size: usize,
+	pub team_health: usize,
+	pub team_strength: usize,
+}
+
+impl RoundStats {
+	fn average(&self, total: usize) -> f32 {
+		total as f32 / self.battles.max(1) as f32
+	}
+
+	fn summary(&self) -> RoundSummary {
+		RoundSummary {
+			round: self.round,
+			battles: self.battles,
+			win_rate: self.average(self.wins),
+			loss_rate: self.average(self.losses),
+			tie_rate: self.average(self.ties),
+			avg_coins: self.average(self.coins),
+			avg_team_size: self.average(self.team_size),
+			avg_health: self.average(self.team_health),
+			avg_strength: self.average(self.team_strength),
+		}
+	}
+}
+
+/// How a given round went on average across all runs, one row of the report
+#[derive(Serialize)]
+pub struct RoundSummary {
+	pub round: usize,
+	pub battles: usize,
+	pub win_rate: f32,
+	pub loss_rate: f32,
+	pub tie_rate: f32,
+	/// Coins in hand when the shop opened
+	pub avg_coins: f32,
+	pub avg_team_size: f32,
+	pub avg_health: f32,
+	pub avg_strength: f32,
+}
+
+#[derive(Serialize)]
+pub struct SimulationReport {
+	pub runs: usize,
+	pub seed: u64,
+	pub victories: usize,
+	pub average_rounds: f32,
+	pub tie_rate: f32,
+	pub rounds: Vec<RoundSummary>,
+}
+
+pub fn simulate(balance: &BalanceConfig, config: &SimulationConfig) -> SimulationReport {
+	let mut rng = StdRng::seed_from_u64(config.seed);
+	let mut rounds: Vec<RoundStats> = Vec::new();
+	let mut victories = 0;
+	let mut total_rounds = 0;
+
+	for _ in 0..config.runs {
+		let mut coins = Coins(balance.run.starting_coins);
+		let mut run = RunProgress::new(balance);
+		let mut team = Vec::new();
+
+		while !run_over(balance, &run) && run.rounds < MAX_ROUNDS {
+			let team_size = balance.team_size(run.rounds);
+			let coins_before = coins.0;
+			run.spent += visit_shop(
+				&mut rng,
+				balance,
+				config.player,
+				run.rounds,
+				team_size,
+				&mut coins,
+				&mut team,
+			);
+
+			let budget = run.spent;
+			run.rounds += 1;
+			let left = records(&team);
+			let right = build_opponent(
+				&mut rng,
+				balance,
+				config.difficulty,
+				run.rounds,
+				team_size,
+				budget,
+				&left,
+			);
+			let Fight { outcome, spent_shields, .. } = fight(&left, &right);
+			pay_out(&balance.rewards, outcome, &mut run, &mut coins);
+			// peppers wear off after the battle, shields stay until they block a hit
+			for (slot, buddy) in team.iter_mut().enumerate() {
+				buddy.health.0.reset();
+				buddy.strength.0.reset();
+				if spent_shields.contains(&slot) {
+					buddy.shielded = false;
+				}
+			}
+
+			if rounds.len() < run.rounds {
+				rounds.push(RoundStats { round: run.rounds, ..Default::default() });
+			}
+			let stats = &mut rounds[run.rounds - 1];
+			stats.battles += 1;
+			match outcome {
+				BattleOutcome::Win => stats.wins += 1,
+				BattleOutcome::Lose => stats.losses += 1,
+				BattleOutcome::Tie => stats.ties += 1,
+			}
+			stats.coins += coins_before;
+			stats.team_size += left.len();
+			stats.team_health += left.iter().map(|record| record.health).sum::<usize>();
+			stats.team_strength += left.iter().map(|record| record.strength).sum::<usize>();
+		}
+
+		if run.won >= balance.run.trophy_target {
+			victories += 1;
+		}
+		total_rounds += run.rounds;
+	}
+
+	let battles = rounds.iter().map(|stats| stats.battles).sum::<usize>();
+	let ties = rounds.iter().map(|stats| stats.ties).sum::<usize>();
+	SimulationReport {
+		runs: config.runs,
+		seed: config.seed,
+		victories,
+		average_rounds: total_rounds as f32 / config.runs.max(1) as f32,
+		tie_rate: ties as f32 / battles.max(1) as f32,
+		rounds: rounds.iter().map(RoundStats::summary).collect(),
+	}
+}
+
+/// One row per round. The run totals are repeated on every row so the file stays a single table.
+pub fn to_csv(report: &SimulationReport) -> String {
+	let mut csv = "round,battles,win_rate,loss_rate,tie_rate,avg_coins,avg_team_size,avg_health,avg_strength,runs,seed,victories,average_rounds\n"
+		.to_string();
+	for round in report.rounds.iter() {
+		csv += &format!(
+			"{},{},{:.3},{:.3},{:.3},{:.2},{:.2},{:.2},{:.2},{},{},{},{:.2}\n",
+			round.round,
+			round.battles,
+			round.win_rate,
+			round.loss_rate,
+			round.tie_rate,
+			round.avg_coins,
+			round.avg_team_size,
+			round.avg_health,
+			round.avg_strength,
+			report.runs,
+			report.seed,
+			report.victories,
+			report.average_rounds,
+		);
+	}
+	csv
+}
+
+fn parse_difficulty(name: &str) -> Result<Difficulty, String> {
+	Difficulty::parse(name).ok_or(format!("unknown difficulty {}, use easy, normal or hard", name))
+}
+
+enum Format {
+	Csv,
+	Json,
+}
+
+/// Entry point for the `simulate` command, `args` are the ones following it
+pub fn run(args: impl Iterator<Item = String>) -> Result<(), String> {
+	let mut config = SimulationConfig {
+		runs: 1000,
+		seed: rand::thread_rng().gen(),
+		difficulty: Difficulty::Normal,
+		player: Difficulty::Normal,
+	};
+	let mut format = Format::Csv;
+	let mut out = None;
+
+	let mut args = args.peekable();
+	while let Some(arg) = args.next() {
+		let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+		match arg.as_str() {
+			"--runs" => config.runs = value()?.parse().map_err(|_| "--runs takes a number")?,
+			"--seed" => config.seed = value()?.parse().map_err(|_| "--seed takes a number")?,
+			"--difficulty" => config.difficulty = parse_difficulty(&value()?)?,
+			"--player" => config.player = parse_difficulty(&value()?)?,
+			"--format" => {
+				format = match value()?.as_str() {
+					"csv" => Format::Csv,
+					"json" => Format::Json,
+					other => return Err(format!("unknown format {}, use csv or json", other)),
+				}
+			},
+			"--out" => out = Some(value()?),
+			other => return Err(format!("unknown argument {}", other)),
+		}
+	}
+
+	let report = simulate(&BalanceConfig::default(), &config);
+	let output = match format {
+		Format::Csv => to_csv(&report),
+		Format::Json => serde_json::to_string_pretty(&report).map_err(|err| err.to_string())?,
+	};
+	match out {
+		Some(path) => std::fs::write(&path, output).map_err(|err| format!("{}: {}", path, err)),
+		None => {
+			print!("{}", output);
+			Ok(())
+		},
+	}
+}
@@ -0,0 +1,392 @@
+use crate::{
+	game::{
+		battle::{BattleEvent, BattleSpeed},
+		buddy::{Buddy, BuddyBodySprite, BuddyColor, BuddyFace, BuddyOutline, BuddyWobble, Side},
+		pause::unpaused,
+		Z_BUDDY,
+	},
+	AppState,
+};
+use bevy::{asset::LoadState, prelude::*};
+use serde::Deserialize;
+use std::{collections::HashMap, time::Duration};
+
+/// Animated buddy skins and hats from `assets/skins.json`. A skin is a texture atlas with idle,
+/// attack, hurt and faint frames drawn in place of a buddy's body and outline, a hat is one more
+/// sprite on top of its face. Either is picked by species, so it can follow a token's traits.
+pub struct SkinPlugin;
+
+impl Plugin for SkinPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<SkinManifest>()
+			.add_startup_system(load_skins)
+			.add_system(dress_buddies)
+			.add_system(animate_skin_events.with_run_criteria(unpaused))
+			.add_system(play_skins.with_run_criteria(unpaused).after(animate_skin_events))
+			.add_system(flip_hats);
+	}
+}
+
+// like the balance, skins can be swapped without rebuilding, builds that can't read files fall
+// back to the copy embedded at compile time
+const SKINS_PATH: &str = "assets/skins.json";
+const DEFAULT_SKINS: &str = include_str!("../../assets/skins.json");
+// the body, outline and face images are drawn at half size, skin tiles and hats are too
+const SKIN_SCALE: f32 = 0.5;
+
+#[derive(Deserialize)]
+pub struct SkinManifest {
+	/// The first skin that matches a buddy's species is used
+	#[serde(default)]
+	pub skins: Vec<SkinConfig>,
+	#[serde(default)]
+	pub hats: Vec<HatConfig>,
+}
+
+/// Which buddies get a skin or hat, anything left out matches every buddy
+#[derive(Deserialize)]
+pub struct Species {
+	pub face: Option<BuddyFace>,
+	/// A color name like "Red"
+	pub color: Option<String>,
+}
+
+impl Species {
+	fn matches(&self, face: &BuddyFace, color: &BuddyColor) -> bool {
+		self.face.is_none_or(|species_face| species_face == *face)
+			&& self.color.as_ref().is_none_or(|name| name.eq_ignore_ascii_case(color.name()))
+	}
+}
+
+#[derive(Deserialize)]
+pub struct SkinConfig {
+	#[serde(flatten)]
+	pub species: Species,
+	/// Path of the atlas image under `assets`
+	pub atlas: String,
+	pub tile_size: [f32; 2],
+	pub columns: usize,
+	pub rows: usize,
+	pub idle: SkinAnimationConfig,
+	/// Missing animations play idle instead
+	pub attack: Option<SkinAnimationConfig>,
+	pub hurt: Option<SkinAnimationConfig>,
+	pub faint: Option<SkinAnimationConfig>,
+}
+
+#[derive(Deserialize)]
+pub struct SkinAnimationConfig {
+	/// Atlas indices, left to right and top to bottom
+	pub frames: Vec<usize>,
+	pub fps: f32,
+}
+
+#[derive(Deserialize)]
+pub struct HatConfig {
+	#[serde(flatten)]
+	pub species: Species,
+	/// Path of the hat image under `assets`
+	pub image: String,
+	/// From the buddy's center, for a buddy facing right
+	#[serde(default)]
+	pub offset: [f32; 2],
+}
+
+impl Default for SkinManifest {
+	fn default() -> Self {
+		let from_disk = if cfg!(target_arch = "wasm32") {
+			None
+		} else {
+			std::fs::read_to_string(SKINS_PATH).ok()
+		};
+
+		if let Some(json) = from_disk {
+			match serde_json::from_str::<SkinManifest>(&json).map_err(|err| err.to_string()) {
+				Ok(manifest) => match manifest.validate() {
+					Ok(()) => return manifest,
+					Err(err) => error!("invalid {}, using built in skins: {}", SKINS_PATH, err),
+				},
+				Err(err) => error!("failed to parse {}, using built in skins: {}", SKINS_PATH, err),
+			}
+		}
+
+		serde_json::from_str(DEFAULT_SKINS).expect("built in skins are valid")
+	}
+}
+
+impl SkinManifest {
+	fn validate(&self) -> Result<(), String> {
+		for skin in self.skins.iter() {
+			let tiles = skin.columns * skin.rows;
+			let animations =
+				[Some(&skin.idle), skin.attack.as_ref(), skin.hurt.as_ref(), skin.faint.as_ref()];
+			for animation in animations.into_iter().flatten() {
+				if animation.frames.is_empty() || animation.fps <= 0.0 {
+					return Err(format!("{} needs frames and a positive fps", skin.atlas));
+				}
+				if let Some(frame) = animation.frames.iter().find(|frame| **frame >= tiles) {
+					return Err(format!("{} has no frame {}", skin.atlas, frame));
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Ordered by which one wins when a buddy gets more than one at once
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SkinAnimation {
+	Idle,
+	Attack,
+	Hurt,
+	Faint,
+}
+
+impl SkinConfig {
+	fn animation(&self, animation: SkinAnimation) -> &SkinAnimationConfig {
+		let config = match animation {
+			SkinAnimation::Idle => None,
+			SkinAnimation::Attack => self.attack.as_ref(),
+			SkinAnimation::Hurt => self.hurt.as_ref(),
+			SkinAnimation::Faint => self.faint.as_ref(),
+		};
+		config.unwrap_or(&self.idle)
+	}
+}
+
+/// The loaded images for the manifest, in the same order
+struct SkinAssets {
+	skins: Vec<(Handle<Image>, Handle<TextureAtlas>)>,
+	hats: Vec<Handle<Image>>,
+}
+
+/// On a buddy wearing a skin
+#[derive(Component)]
+pub struct BuddySkin {
+	sprite: Entity,
+}
+
+/// On a buddy wearing a hat
+#[derive(Component)]
+pub struct BuddyHat;
+
+/// On the body and outline sprites a skin is drawn in place of, so showing the buddy again
+/// doesn't bring them back
+#[derive(Component)]
+pub struct SkinHidden;
+
+#[derive(Component)]
+pub struct SkinSprite {
+	buddy: Entity,
+	skin: usize,
+	animation: SkinAnimation,
+	frame: usize,
+	timer: Timer,
+}
+
+impl SkinSprite {
+	fn play(&mut self, animation: SkinAnimation, config: &SkinConfig) {
+		self.animation = animation;
+		self.frame = 0;
+		self.timer = frame_timer(config.animation(animation));
+	}
+}
+
+#[derive(Component)]
+pub struct HatSprite {
+	buddy: Entity,
+	offset: Vec2,
+}
+
+fn frame_timer(animation: &SkinAnimationConfig) -> Timer {
+	Timer::new(Duration::from_secs_f32(1.0 / animation.fps), true)
+}
+
+fn load_skins(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	manifest: Res<SkinManifest>,
+	mut atlases: ResMut<Assets<TextureAtlas>>,
+) {
+	let skins = manifest
+		.skins
+		.iter()
+		.map(|skin| {
+			let image = asset_server.load(skin.atlas.as_str());
+			let atlas = TextureAtlas::from_grid(
+				image.clone(),
+				Vec2::from(skin.tile_size),
+				skin.columns,
+				skin.rows,
+			);
+			(image, atlases.add(atlas))
+		})
+		.collect();
+	let hats = manifest.hats.iter().map(|hat| asset_server.load(hat.image.as_str())).collect();
+	commands.insert_resource(SkinAssets { skins, hats });
+}
+
+// a skin or hat is put on once its image has loaded, one that fails to load leaves the buddy as
+// it is
+fn dress_buddies(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	manifest: Res<SkinManifest>,
+	skin_assets: Res<SkinAssets>,
+	buddies: Query<
+		(Entity, &BuddyFace, &BuddyColor, &Children, Option<&BuddySkin>, Option<&BuddyHat>),
+		With<Buddy>,
+	>,
+	wobbles: Query<&Children, With<BuddyWobble>>,
+	mut plain_sprites: Query<&mut Visibility, Or<(With<BuddyBodySprite>, With<BuddyOutline>)>>,
+) {
+	let loaded = |image: &Handle<Image>| asset_server.get_load_state(image) == LoadState::Loaded;
+	for (buddy, face, color, children, skin, hat) in buddies.iter() {
+		let (wobble, parts) =
+			match children.iter().find_map(|child| Some((*child, wobbles.get(*child).ok()?))) {
+				Some(wobble) => wobble,
+				None => continue,
+			};
+
+		let skin_index = manifest.skins.iter().position(|skin| skin.species.matches(face, color));
+		if let (None, Some(index)) = (skin, skin_index) {
+			let (image, atlas) = &skin_assets.skins[index];
+			if loaded(image) {
+				let config = &manifest.skins[index];
+				let skin_sprite = SkinSprite {
+					buddy,
+					skin: index,
+					animation: SkinAnimation::Idle,
+					frame: 0,
+					timer: frame_timer(&config.idle),
+				};
+				let sprite = commands
+					.spawn_bundle(SpriteSheetBundle {
+						texture_atlas: atlas.clone(),
+						sprite: TextureAtlasSprite::new(config.idle.frames[0]),
+						transform: Transform::from_xyz(0.0, 0.0, Z_BUDDY)
+							.with_scale(Vec3::splat(SKIN_SCALE)),
+						..Default::default()
+					})
+					.insert(skin_sprite)
+					.id();
+				commands.entity(wobble).add_child(sprite);
+				commands.entity(buddy).insert(BuddySkin { sprite });
+				for part in parts.iter() {
+					if let Ok(mut visibility) = plain_sprites.get_mut(*part) {
+						visibility.is_visible = false;
+						commands.entity(*part).insert(SkinHidden);
+					}
+				}
+			}
+		}
+
+		let hat_index = manifest.hats.iter().position(|hat| hat.species.matches(face, color));
+		if let (None, Some(index)) = (hat, hat_index) {
+			let image = &skin_assets.hats[index];
+			if loaded(image) {
+				let offset = Vec2::from(manifest.hats[index].offset);
+				let sprite = commands
+					.spawn_bundle(SpriteBundle {
+						texture: image.clone(),
+						// above the face
+						transform: Transform::from_translation(offset.extend(Z_BUDDY + 0.25))
+							.with_scale(Vec3::splat(SKIN_SCALE)),
+						..Default::default()
+					})
+					.insert(HatSprite { buddy, offset })
+					.id();
+				commands.entity(wobble).add_child(sprite);
+				commands.entity(buddy).insert(BuddyHat);
+			}
+		}
+	}
+}
+
+fn animate_skin_events(
+	manifest: Res<SkinManifest>,
+	mut battle_events: EventReader<BattleEvent>,
+	skins: Query<&BuddySkin>,
+	mut sprites: Query<&mut SkinSprite>,
+) {
+	// both buddies attack at once, getting hurt shows over attacking
+	let mut played = HashMap::new();
+	let mut play = |buddy: Entity, animation: SkinAnimation| {
+		let current = played.entry(buddy).or_insert(animation);
+		*current = animation.max(*current);
+	};
+	for event in battle_events.iter() {
+		match event {
+			BattleEvent::Hit { attacker, target, blocked, .. } => {
+				play(*attacker, SkinAnimation::Attack);
+				if !blocked {
+					play(*target, SkinAnimation::Hurt);
+				}
+			},
+			BattleEvent::Fainted { buddy } => play(*buddy, SkinAnimation::Faint),
+			BattleEvent::Buffed { .. } | BattleEvent::Finished(_) => {},
+		}
+	}
+	for (buddy, animation) in played {
+		let sprite = skins.get(buddy).and_then(|skin| sprites.get_mut(skin.sprite));
+		if let Ok(mut sprite) = sprite {
+			let config = &manifest.skins[sprite.skin];
+			sprite.play(animation, config);
+		}
+	}
+}
+
+fn play_skins(
+	time: Res<Time>,
+	state: Res<State<AppState>>,
+	speed: Res<BattleSpeed>,
+	manifest: Res<SkinManifest>,
+	buddies: Query<&Side, With<Buddy>>,
+	mut sprites: Query<(&mut SkinSprite, &mut TextureAtlasSprite)>,
+) {
+	let delta = if state.current().is_battle() { speed.delta(time.delta()) } else { time.delta() };
+	for (mut skin_sprite, mut sprite) in sprites.iter_mut() {
+		let config = &manifest.skins[skin_sprite.skin];
+		let frames = &config.animation(skin_sprite.animation).frames;
+		let ticks = skin_sprite.timer.tick(delta).times_finished();
+		for _ in 0..ticks {
+			if skin_sprite.frame + 1 < frames.len() {
+				skin_sprite.frame += 1;
+			} else {
+				match skin_sprite.animation {
+					SkinAnimation::Idle => skin_sprite.frame = 0,
+					// stays down until the buddy is gone
+					SkinAnimation::Faint => {},
+					SkinAnimation::Attack | SkinAnimation::Hurt => {
+						skin_sprite.play(SkinAnimation::Idle, config)
+					},
+				}
+			}
+		}
+
+		let frames = &config.animation(skin_sprite.animation).frames;
+		let index = frames[skin_sprite.frame.min(frames.len() - 1)];
+		if sprite.index != index {
+			sprite.index = index;
+		}
+		// faces the same way as the face sprite
+		let flip_x = !matches!(buddies.get(skin_sprite.buddy), Ok(Side::Left));
+		if sprite.flip_x != flip_x {
+			sprite.flip_x = flip_x;
+		}
+	}
+}
+
+fn flip_hats(
+	buddies: Query<&Side, With<Buddy>>,
+	mut hats: Query<(&HatSprite, &mut Sprite, &mut Transform)>,
+) {
+	for (hat, mut sprite, mut transform) in hats.iter_mut() {
+		let flip_x = !matches!(buddies.get(hat.buddy), Ok(Side::Left));
+		if sprite.flip_x != flip_x {
+			sprite.flip_x = flip_x;
+			let x = if flip_x { -hat.offset.x } else { hat.offset.x };
+			transform.translation.x = x;
+		}
+	}
+}
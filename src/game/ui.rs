@@ -1,4 +1,4 @@
-use crate::game::counters::{spawn_coins_element, spawn_trophies_element};
+use crate::game::counters::{spawn_coins_element, spawn_lives_element, spawn_trophies_element};
 use bevy::prelude::*;
 
 #[derive(Component)]
@@ -21,5 +21,6 @@ pub fn spawn_ui(commands: &mut Commands, asset_server: &AssetServer) {
 		.with_children(|parent| {
 			spawn_coins_element(parent, asset_server);
 			spawn_trophies_element(parent, asset_server);
+			spawn_lives_element(parent, asset_server);
 		});
 }
@@ -0,0 +1,268 @@
+use bevy::prelude::*;
+
+use crate::{
+	game::{counters::Trophies, daily::today},
+	menu::{back_to_menu, BackButton},
+	network::{Network, NetworkEvent},
+	types::{Leaderboard, LeaderboardEntry, NetworkMessage, NetworkMessageResponse},
+	widget::spawn_button,
+	AppState,
+};
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_system_set(
+			SystemSet::on_enter(AppState::Leaderboard).with_system(spawn_leaderboards),
+		)
+		.add_system_set(
+			SystemSet::on_update(AppState::Leaderboard)
+				.with_system(fetch_leaderboards)
+				.with_system(back_to_menu),
+		)
+		.add_system_set(
+			SystemSet::on_exit(AppState::Leaderboard).with_system(cleanup_leaderboards),
+		);
+	}
+}
+
+// how many entries are fetched for each board
+const TOP_COUNT: u32 = 10;
+// servers that don't know about leaderboards never answer
+const FETCH_TIMEOUT: f32 = 3.0;
+
+struct LeaderboardData {
+	root: Entity,
+}
+
+/// Boards are asked for one at a time while the screen is up, so a slow or missing answer never
+/// holds up the game
+struct LeaderboardFetch {
+	boards: Vec<Leaderboard>,
+	/// The board being asked for, the ones before it are filled in
+	next: usize,
+	requested: bool,
+	timeout: Timer,
+}
+
+/// Holds the lines of the board at this index, filled in once its answer is in
+#[derive(Component)]
+struct BoardLines(usize);
+
+fn board_title(board: &Leaderboard) -> String {
+	match board {
+		Leaderboard::Wins => "All-time wins".to_string(),
+		Leaderboard::BestRun => "Best run".to_string(),
+		Leaderboard::Daily(date) => format!("Daily {}", date),
+	}
+}
+
+/// `None` if there was no answer
+fn board_lines(entries: Option<(Vec<LeaderboardEntry>, Option<LeaderboardEntry>)>) -> Vec<String> {
+	let (top, own) = match entries {
+		Some(entries) => entries,
+		None => return vec!["Offline".to_string()],
+	};
+
+	let mut lines = top
+		.iter()
+		.map(|entry| format!("{}. {}  {}", entry.rank, entry.name, entry.score))
+		.collect::<Vec<_>>();
+	if top.is_empty() {
+		lines.push("Nobody yet".to_string());
+	}
+	lines.push(match own {
+		Some(own) => format!("You: #{}  {}", own.rank, own.score),
+		None => "You: unranked".to_string(),
+	});
+	lines
+}
+
+fn line_style(asset_server: &AssetServer, font_size: f32) -> TextStyle {
+	TextStyle {
+		font: asset_server.load("font/CaveatBrush-Regular.ttf"),
+		font_size,
+		color: Color::hex("323232").unwrap(),
+	}
+}
+
+fn spawn_board_lines(parent: &mut ChildBuilder, asset_server: &AssetServer, lines: Vec<String>) {
+	for line in lines {
+		parent.spawn_bundle(TextBundle {
+			text: Text::with_section(line, line_style(asset_server, 30.0), Default::default()),
+			..Default::default()
+		});
+	}
+}
+
+fn spawn_leaderboards(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	trophies: Res<Trophies>,
+) {
+	let mut boards = vec![Leaderboard::Wins, Leaderboard::BestRun];
+	if let Some(day) = today() {
+		boards.push(Leaderboard::Daily(day.date));
+	}
+
+	let text_style = |font_size| line_style(&asset_server, font_size);
+	// ui is laid out bottom to top, so columns list their children in reverse
+	let column = |margin| Style {
+		flex_direction: FlexDirection::ColumnReverse,
+		align_items: AlignItems::Center,
+		margin,
+		..Default::default()
+	};
+
+	let root = commands
+		.spawn_bundle(NodeBundle {
+			style: Style {
+				size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+				justify_content: JustifyContent::Center,
+				..column(Rect::default())
+			},
+			color: Color::rgba(1.0, 1.0, 1.0, 0.8).into(),
+			..Default::default()
+		})
+		.with_children(|parent| {
+			parent.spawn_bundle(TextBundle {
+				text: Text::with_section("Leaderboards", text_style(90.0), Default::default()),
+				..Default::default()
+			});
+			parent.spawn_bundle(TextBundle {
+				text: Text::with_section(
+					format!("Your record: {} wins in {} battles", trophies.won, trophies.rounds),
+					text_style(40.0),
+					Default::default(),
+				),
+				..Default::default()
+			});
+			parent
+				.spawn_bundle(NodeBundle {
+					style: Style { align_items: AlignItems::FlexStart, ..Default::default() },
+					color: Color::NONE.into(),
+					..Default::default()
+				})
+				.with_children(|parent| {
+					for (index, board) in boards.iter().enumerate() {
+						parent
+							.spawn_bundle(NodeBundle {
+								style: column(Rect::all(Val::Px(30.0))),
+								color: Color::NONE.into(),
+								..Default::default()
+							})
+							.with_children(|parent| {
+								parent.spawn_bundle(TextBundle {
+									text: Text::with_section(
+										board_title(board),
+										text_style(50.0),
+										Default::default(),
+									),
+									..Default::default()
+								});
+								parent
+									.spawn_bundle(NodeBundle {
+										style: column(Rect::default()),
+										color: Color::NONE.into(),
+										..Default::default()
+									})
+									.insert(BoardLines(index))
+									.with_children(|parent| {
+										let loading = vec!["Loading...".to_string()];
+										spawn_board_lines(parent, &asset_server, loading);
+									});
+							});
+					}
+				});
+			spawn_button(parent, &asset_server, "Back", 150.0, BackButton);
+		})
+		.id();
+
+	commands.insert_resource(LeaderboardData { root });
+	commands.insert_resource(LeaderboardFetch {
+		boards,
+		next: 0,
+		requested: false,
+		timeout: Timer::from_seconds(FETCH_TIMEOUT, false),
+	});
+}
+
+/// Asks for each board in turn and fills it in once the answer is in. Once the server fails to
+/// answer, the boards still left show as offline.
+fn fetch_leaderboards(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	time: Res<Time>,
+	mut network: ResMut<Network>,
+	mut events: EventReader<NetworkEvent>,
+	mut fetch: ResMut<LeaderboardFetch>,
+	board_lines_query: Query<(Entity, &BoardLines, Option<&Children>)>,
+) {
+	let fetch = &mut *fetch;
+	let board = match fetch.boards.get(fetch.next) {
+		Some(board) => board.clone(),
+		None => return,
+	};
+
+	let answer = if !fetch.requested {
+		let request = NetworkMessage::FetchLeaderboard(board.clone(), TOP_COUNT);
+		fetch.requested = true;
+		fetch.timeout.reset();
+		network.try_send_message(&request).map(|_| None)
+	} else {
+		let mut answer = Ok(None);
+		for event in events.iter() {
+			match event {
+				NetworkEvent::Response(NetworkMessageResponse::Leaderboard(answered, top, own))
+					if *answered == board =>
+				{
+					answer = Ok(Some((top.clone(), own.clone())));
+				},
+				NetworkEvent::Disconnected(err) => answer = Err(err.clone()),
+				// answers to someone else, or to an earlier visit
+				NetworkEvent::Response(_) => {},
+			}
+		}
+		match answer {
+			Ok(None) if fetch.timeout.tick(time.delta()).finished() => {
+				Err("no answer from the server".to_string())
+			},
+			answer => answer,
+		}
+	};
+
+	let filled = match answer {
+		Ok(None) => return,
+		Ok(Some(entries)) => {
+			let index = fetch.next;
+			fetch.next += 1;
+			fetch.requested = false;
+			vec![(index, Some(entries))]
+		},
+		Err(err) => {
+			warn!("couldn't fetch the leaderboards: {}", err);
+			let offline = (fetch.next..fetch.boards.len()).map(|index| (index, None)).collect();
+			fetch.next = fetch.boards.len();
+			offline
+		},
+	};
+	for (index, entries) in filled {
+		for (entity, lines, children) in board_lines_query.iter() {
+			if lines.0 != index {
+				continue;
+			}
+			for child in children.iter().flat_map(|children| children.iter()) {
+				commands.entity(*child).despawn_recursive();
+			}
+			commands.entity(entity).with_children(|parent| {
+				spawn_board_lines(parent, &asset_server, board_lines(entries));
+			});
+			break;
+		}
+	}
+}
+
+fn cleanup_leaderboards(mut commands: Commands, leaderboard: Res<LeaderboardData>) {
+	commands.entity(leaderboard.root).despawn_recursive();
+}
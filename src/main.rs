@@ -1,25 +1,69 @@
 #![allow(clippy::type_complexity, clippy::too_many_arguments)]
 
+mod actions;
+mod collection;
 mod connect;
 mod game;
+mod leaderboard;
 mod menu;
 mod network;
+mod settings;
+mod sound;
+mod tooltip;
+mod tween;
 mod types;
+mod view;
+mod widget;
 
 use crate::{game::GamePlugin, menu::MenuPlugin};
+use actions::ActionsPlugin;
 use bevy::prelude::*;
+use collection::CollectionPlugin;
 use connect::ConnectPlugin;
+use leaderboard::LeaderboardPlugin;
 use network::NetworkPlugin;
+use settings::{Settings, SettingsPlugin};
+use sound::{SoundOutput, SoundPlugin};
+use tooltip::TooltipPlugin;
+use tween::TweenPlugin;
+use view::ViewPlugin;
+use widget::WidgetPlugin;
 
 fn main() {
+	// `blockchain_buddy simulate ...` plays balance test runs without opening a window
+	if std::env::args().nth(1).as_deref() == Some("simulate") {
+		if let Err(err) = game::simulate::run(std::env::args().skip(2)) {
+			eprintln!("simulate: {}", err);
+			std::process::exit(1);
+		}
+		return;
+	}
+
+	let settings = Settings::load();
+	// `--mute` for machines without an audio device
+	let sound_output = if std::env::args().any(|arg| arg == "--mute") {
+		SoundOutput::Silent
+	} else {
+		SoundOutput::Speakers
+	};
 	App::new()
-		.insert_resource(WindowDescriptor { ..Default::default() })
+		.insert_resource(settings.window_descriptor())
+		.insert_resource(settings)
 		.insert_resource(ClearColor(Color::rgb(0.8, 0.8, 0.9)))
 		.add_state(AppState::LoadNetwork)
 		.add_plugins(DefaultPlugins)
 		.add_plugin(NetworkPlugin)
 		.add_plugin(ConnectPlugin)
+		.add_plugin(ActionsPlugin)
+		.add_plugin(WidgetPlugin)
+		.add_plugin(TooltipPlugin)
+		.add_plugin(TweenPlugin)
+		.add_plugin(ViewPlugin)
 		.add_plugin(MenuPlugin)
+		.add_plugin(CollectionPlugin)
+		.add_plugin(LeaderboardPlugin)
+		.add_plugin(SettingsPlugin)
+		.add_plugin(SoundPlugin { output: sound_output })
 		.add_plugin(GamePlugin)
 		.add_startup_system(setup)
 		.run();
@@ -31,9 +75,26 @@ pub enum AppState {
 	Connect,
 	Login,
 	Menu,
+	/// The buddies this account owns
+	Collection,
+	/// The player's record and the server leaderboards, reached from the menu
+	Leaderboard,
+	Settings,
 	Startup,
 	Shop,
 	Battle,
+	RunSummary,
+	/// Plays a recorded battle back on top of the paused shop
+	Replay,
+	/// The pause menu, pushed over the shop, a battle or a replay
+	Paused,
+}
+
+impl AppState {
+	/// Both live battles and replays show the teams lined up against each other
+	pub fn is_battle(&self) -> bool {
+		matches!(self, AppState::Battle | AppState::Replay)
+	}
 }
 
 fn setup(mut commands: Commands) {
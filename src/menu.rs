@@ -1,6 +1,17 @@
-use bevy::prelude::*;
+use bevy::{app::AppExit, prelude::*};
 
-use crate::AppState;
+use crate::{
+	game::{
+		balance::BalanceConfig,
+		counters::{Coins, RunProgress},
+		daily::{today, DailyChallenge},
+		pause::SavedTeam,
+		rng::GameRng,
+		run::{can_continue, reset_run},
+	},
+	widget::{spawn_button, ButtonPressed},
+	AppState,
+};
 
 pub struct MenuPlugin;
 
@@ -13,73 +24,166 @@ impl Plugin for MenuPlugin {
 }
 
 struct MenuData {
-	button_entity: Entity,
+	root: Entity,
 }
 
+#[derive(Component, Clone, Copy)]
+pub enum MenuButton {
+	/// Picks up the saved run, and the coins and trophies saved on the server. Only shown while
+	/// there is a run to pick up.
+	Continue,
+	NewRun,
+	/// Starts a fresh run of today's daily challenge
+	Daily,
+	Collection,
+	Leaderboard,
+	Settings,
+	Quit,
+}
+
+impl MenuButton {
+	fn label(&self) -> &'static str {
+		match self {
+			MenuButton::Continue => "Continue",
+			MenuButton::NewRun => "New run",
+			MenuButton::Daily => "Daily challenge",
+			MenuButton::Collection => "Collection",
+			MenuButton::Leaderboard => "Leaderboards",
+			MenuButton::Settings => "Settings",
+			MenuButton::Quit => "Quit",
+		}
+	}
+}
+
+/// Returns to the main menu, escape and the gamepad's east button do the same
 #[derive(Component)]
-pub struct PlayButton;
+pub struct BackButton;
 
-pub const NORMAL_BUTTON: Color = Color::rgb(0.4, 0.4, 0.8);
-pub const HOVERED_BUTTON: Color = Color::rgb(0.6, 0.6, 0.9);
+fn spawn_menu(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	balance: Res<BalanceConfig>,
+	run: Res<RunProgress>,
+	saved_team: Res<SavedTeam>,
+) {
+	let mut buttons = Vec::new();
+	if can_continue(&balance, &run, &saved_team) {
+		buttons.push(MenuButton::Continue);
+	}
+	buttons.push(MenuButton::NewRun);
+	if today().is_some() {
+		buttons.push(MenuButton::Daily);
+	}
+	buttons.extend([MenuButton::Collection, MenuButton::Leaderboard, MenuButton::Settings]);
+	// browsers close tabs on their own
+	if !cfg!(target_arch = "wasm32") {
+		buttons.push(MenuButton::Quit);
+	}
 
-fn spawn_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
-	let button_entity = commands
-		.spawn_bundle(ButtonBundle {
+	let root = commands
+		.spawn_bundle(NodeBundle {
 			style: Style {
-				size: Size::new(Val::Px(150.0), Val::Px(65.0)),
-				// center button
-				margin: Rect::all(Val::Auto),
-				// horizontally center child text
+				size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+				// ui is laid out bottom to top, so the first button comes first
+				flex_direction: FlexDirection::ColumnReverse,
 				justify_content: JustifyContent::Center,
-				// vertically center child text
 				align_items: AlignItems::Center,
 				..Default::default()
 			},
-			color: NORMAL_BUTTON.into(),
+			color: Color::NONE.into(),
 			..Default::default()
 		})
-		.insert(PlayButton)
 		.with_children(|parent| {
-			parent.spawn_bundle(TextBundle {
-				text: Text::with_section(
-					"Play",
-					TextStyle {
-						font: asset_server.load("font/AmaticSC-Bold.ttf"),
-						font_size: 40.0,
-						color: Color::rgb(0.9, 0.9, 0.9),
-					},
-					Default::default(),
-				),
-				..Default::default()
-			});
+			for button in buttons {
+				spawn_button(parent, &asset_server, button.label(), 250.0, button);
+			}
 		})
 		.id();
 
-	commands.insert_resource(MenuData { button_entity });
+	commands.insert_resource(MenuData { root });
 }
 
 fn menu(
 	mut state: ResMut<State<AppState>>,
-	mut interaction_query: Query<
-		(&Interaction, &mut UiColor),
-		(Changed<Interaction>, With<PlayButton>),
-	>,
+	mut exit: EventWriter<AppExit>,
+	mut pressed: EventReader<ButtonPressed>,
+	balance: Res<BalanceConfig>,
+	mut coins: ResMut<Coins>,
+	mut run: ResMut<RunProgress>,
+	mut daily: ResMut<DailyChallenge>,
+	mut game_rng: ResMut<GameRng>,
+	mut saved_team: ResMut<SavedTeam>,
+	buttons: Query<&MenuButton>,
 ) {
-	for (interaction, mut color) in interaction_query.iter_mut() {
-		match *interaction {
-			Interaction::Clicked => {
+	for ButtonPressed(button) in pressed.iter() {
+		let button = match buttons.get(*button) {
+			Ok(button) => *button,
+			Err(_) => continue,
+		};
+		match button {
+			MenuButton::Continue => state.set(AppState::Startup).unwrap(),
+			MenuButton::NewRun => {
+				reset_run(
+					&balance,
+					&mut coins,
+					&mut run,
+					&mut daily,
+					&mut game_rng,
+					&mut saved_team,
+					None,
+				);
 				state.set(AppState::Startup).unwrap();
 			},
-			Interaction::Hovered => {
-				*color = HOVERED_BUTTON.into();
-			},
-			Interaction::None => {
-				*color = NORMAL_BUTTON.into();
+			MenuButton::Daily => {
+				if let Some(day) = today() {
+					// everyone starts the challenge from scratch, whatever was saved
+					reset_run(
+						&balance,
+						&mut coins,
+						&mut run,
+						&mut daily,
+						&mut game_rng,
+						&mut saved_team,
+						Some(day),
+					);
+					state.set(AppState::Startup).unwrap();
+				}
 			},
+			MenuButton::Collection => state.set(AppState::Collection).unwrap(),
+			MenuButton::Leaderboard => state.set(AppState::Leaderboard).unwrap(),
+			MenuButton::Settings => state.set(AppState::Settings).unwrap(),
+			MenuButton::Quit => exit.send(AppExit),
 		}
 	}
 }
 
+/// Add to the update set of any screen with a `BackButton`. Screens pushed over another state,
+/// like settings opened from the pause menu, go back to that state instead.
+pub fn back_to_menu(
+	mut state: ResMut<State<AppState>>,
+	mut keys: ResMut<Input<KeyCode>>,
+	gamepads: Res<Gamepads>,
+	mut gamepad_buttons: ResMut<Input<GamepadButton>>,
+	mut pressed: EventReader<ButtonPressed>,
+	back_buttons: Query<(), With<BackButton>>,
+) {
+	// consumed so the screen gone back to doesn't see the same press
+	let mut back = keys.clear_just_pressed(KeyCode::Escape);
+	for gamepad in gamepads.iter() {
+		back |=
+			gamepad_buttons.clear_just_pressed(GamepadButton(*gamepad, GamepadButtonType::East));
+	}
+	back |= pressed.iter().any(|ButtonPressed(button)| back_buttons.get(*button).is_ok());
+	if !back {
+		return;
+	}
+	if state.inactives().is_empty() {
+		state.set(AppState::Menu).unwrap();
+	} else {
+		state.pop().unwrap();
+	}
+}
+
 fn cleanup_menu(mut commands: Commands, menu_data: Res<MenuData>) {
-	commands.entity(menu_data.button_entity).despawn_recursive();
+	commands.entity(menu_data.root).despawn_recursive();
 }
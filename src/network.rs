@@ -1,8 +1,15 @@
 use bevy::prelude::*;
-use std::io::{Read, Write};
+use std::io::{Cursor, ErrorKind, Read, Write};
 use std::net::TcpStream;
 
-use crate::game::counters::{Coins, Trophies};
+use crate::game::{
+	buddy::Buddy,
+	counters::{Coins, RunProgress, Trophies},
+	daily::DailyChallenge,
+	pause::{SavedTeam, TeamBuddy},
+	rng::GameRng,
+	run::save_run,
+};
 use crate::types::{
 	GameId, GenericNetworkMessage, NetworkMessage, NetworkMessageResponse, UserData,
 };
@@ -12,23 +19,36 @@ pub struct NetworkPlugin;
 
 impl Plugin for NetworkPlugin {
 	fn build(&self, app: &mut App) {
-		app.insert_resource(Network::new())
+		app.add_event::<NetworkEvent>()
+			.insert_resource(Network::new())
 			.insert_resource(ShouldSaveGame(false))
 			.add_system_set(SystemSet::on_enter(AppState::LoadNetwork).with_system(enter_state))
 			.add_system_set(SystemSet::on_exit(AppState::LoadNetwork).with_system(exit_state))
-			.add_system(enter_save_data_state);
+			.add_system(enter_save_data_state)
+			.add_system(receive_messages);
 	}
 }
 
+/// What the server sent, read every frame by `receive_messages`. Each screen picks out the
+/// answers it asked for, so one waiting for an answer never swallows another's. Answers nobody
+/// waits for anymore are dropped along with the event.
+pub enum NetworkEvent {
+	Response(NetworkMessageResponse),
+	/// Reading failed and the connection was dropped, nothing more is coming
+	Disconnected(String),
+}
+
 pub struct Network {
 	connection: Option<TcpStream>,
+	/// Bytes read that don't make up a whole message yet
+	received: Vec<u8>,
 }
 
 pub struct ShouldSaveGame(pub bool);
 
 impl Network {
 	pub fn new() -> Self {
-		Self { connection: None }
+		Self { connection: None, received: Vec::new() }
 	}
 
 	pub fn send_message(&mut self, msg: &NetworkMessage) {
@@ -40,17 +60,80 @@ impl Network {
 		self.send_message_internal(encoded.as_slice());
 	}
 
+	/// Waits until a whole message has arrived, however many reads that takes
 	pub fn read_message(&mut self) -> Option<NetworkMessageResponse> {
-		if let Some(conn) = &mut self.connection {
+		loop {
+			if let Some(message) = self.take_message().unwrap() {
+				return Some(message);
+			}
+			let conn = self.connection.as_mut()?;
 			let mut buf = [0; 2048];
 			let count = conn.read(&mut buf).unwrap();
-			let buf = buf[0..count].to_vec();
+			if count == 0 {
+				return None;
+			}
+			self.received.extend_from_slice(&buf[0..count]);
+		}
+	}
+
+	/// Like `send_message`, but a missing or broken connection is an error instead of a panic
+	pub fn try_send_message(&mut self, msg: &NetworkMessage) -> Result<(), String> {
+		let data = bincode::serialize(&msg).map_err(|err| err.to_string())?;
+		let msg = GenericNetworkMessage { game_id: GameId::BlockchainBuddy, data };
+		let encoded = bincode::serialize(&msg).map_err(|err| err.to_string())?;
+		let conn = self.connection.as_mut().ok_or("not connected")?;
+		conn.write_all(&encoded).map_err(|err| err.to_string())
+	}
 
-			let decoded: NetworkMessageResponse = bincode::deserialize(&buf).unwrap();
-			return Some(decoded);
+	/// Reads whatever has arrived without waiting and hands out the next whole message, if
+	/// there is one. A closed connection or a reply that doesn't decode is an error.
+	fn poll_message(&mut self) -> Result<Option<NetworkMessageResponse>, String> {
+		let conn = self.connection.as_mut().ok_or("not connected")?;
+		let mut buf = [0; 2048];
+		conn.set_nonblocking(true).map_err(|err| err.to_string())?;
+		let mut closed = false;
+		let read = loop {
+			match conn.read(&mut buf) {
+				Ok(0) => {
+					closed = true;
+					break Ok(());
+				},
+				Ok(count) => self.received.extend_from_slice(&buf[0..count]),
+				Err(err) if err.kind() == ErrorKind::WouldBlock => break Ok(()),
+				Err(err) => break Err(err.to_string()),
+			}
+		};
+		// everything else still waits for its answers
+		conn.set_nonblocking(false).map_err(|err| err.to_string())?;
+		read?;
+		match self.take_message()? {
+			None if closed => Err("connection closed".to_string()),
+			message => Ok(message),
 		}
+	}
 
-		None
+	/// Decodes the first message in `received` and drops its bytes. A message that is only
+	/// partly there stays until the rest arrives.
+	fn take_message(&mut self) -> Result<Option<NetworkMessageResponse>, String> {
+		if self.received.is_empty() {
+			return Ok(None);
+		}
+		let mut cursor = Cursor::new(&self.received[..]);
+		match bincode::deserialize_from(&mut cursor) {
+			Ok(message) => {
+				let used = cursor.position() as usize;
+				self.received.drain(0..used);
+				Ok(Some(message))
+			},
+			Err(err) => match *err {
+				bincode::ErrorKind::Io(ref io) if io.kind() == ErrorKind::UnexpectedEof => Ok(None),
+				_ => {
+					// nothing after a broken message can be trusted either
+					self.received.clear();
+					Err(err.to_string())
+				},
+			},
+		}
 	}
 
 	fn send_message_internal(&mut self, buf: &[u8]) {
@@ -60,9 +143,24 @@ impl Network {
 	}
 }
 
+fn receive_messages(mut network: ResMut<Network>, mut events: EventWriter<NetworkEvent>) {
+	while network.connection.is_some() {
+		match network.poll_message() {
+			Ok(Some(response)) => events.send(NetworkEvent::Response(response)),
+			Ok(None) => break,
+			Err(err) => {
+				network.connection = None;
+				network.received.clear();
+				events.send(NetworkEvent::Disconnected(err));
+			},
+		}
+	}
+}
+
 pub fn enter_state(mut state: ResMut<State<AppState>>, mut res: ResMut<Network>) {
 	let stream = TcpStream::connect("127.0.0.1:8040");
 	res.connection = stream.ok();
+	res.received.clear();
 
 	state.set(AppState::Connect).unwrap();
 }
@@ -73,9 +171,16 @@ pub fn enter_save_data_state(
 	mut save_game: ResMut<ShouldSaveGame>,
 	coins: Res<Coins>,
 	trophies: Res<Trophies>,
+	run: Res<RunProgress>,
+	team: Query<TeamBuddy, With<Buddy>>,
+	saved_team: Res<SavedTeam>,
+	game_rng: Res<GameRng>,
+	daily: Res<DailyChallenge>,
 	mut network: ResMut<Network>,
 ) {
 	if save_game.0 == true {
+		// the server only knows the account, the run is kept on this machine
+		save_run(&run, &team, &saved_team, &game_rng, &daily);
 		let msg = NetworkMessage::Save(UserData {
 			coins: coins.0 as u32,
 			trophies: (trophies.won as u32, trophies.rounds as u32),
@@ -0,0 +1,320 @@
+use bevy::{
+	prelude::*,
+	window::{PresentMode, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	actions::ActionMap,
+	game::opponent::Difficulty,
+	menu::{back_to_menu, BackButton},
+	widget::{set_button_label, spawn_button, ButtonPressed},
+	AppState,
+};
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<Settings>()
+			.add_system(apply_settings)
+			.add_system_set(SystemSet::on_enter(AppState::Settings).with_system(spawn_settings))
+			.add_system_set(
+				SystemSet::on_update(AppState::Settings)
+					.with_system(settings_buttons)
+					.with_system(back_to_menu),
+			)
+			.add_system_set(SystemSet::on_exit(AppState::Settings).with_system(cleanup_settings));
+	}
+}
+
+// next to the executable's working directory, like the balance config
+const SETTINGS_PATH: &str = "settings.json";
+
+const RESOLUTIONS: &[(u32, u32)] = &[(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+const VOLUME_STEP: u32 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum WindowSetting {
+	Windowed,
+	Borderless,
+	Fullscreen,
+}
+
+impl WindowSetting {
+	fn name(&self) -> &'static str {
+		match self {
+			WindowSetting::Windowed => "Windowed",
+			WindowSetting::Borderless => "Borderless",
+			WindowSetting::Fullscreen => "Fullscreen",
+		}
+	}
+
+	fn next(&self) -> Self {
+		match self {
+			WindowSetting::Windowed => WindowSetting::Borderless,
+			WindowSetting::Borderless => WindowSetting::Fullscreen,
+			WindowSetting::Fullscreen => WindowSetting::Windowed,
+		}
+	}
+
+	fn mode(&self) -> WindowMode {
+		match self {
+			WindowSetting::Windowed => WindowMode::Windowed,
+			WindowSetting::Borderless => WindowMode::BorderlessFullscreen,
+			// keeps the chosen resolution rather than the monitor's largest one
+			WindowSetting::Fullscreen => WindowMode::SizedFullscreen,
+		}
+	}
+}
+
+/// Player preferences, saved to `settings.json` whenever they change. Volumes are percentages.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+	pub window: WindowSetting,
+	pub resolution: (u32, u32),
+	pub vsync: bool,
+	pub master_volume: u32,
+	pub music_volume: u32,
+	pub sfx_volume: u32,
+	/// Buddies sway and their outlines jitter, some players find it distracting
+	pub wobble: bool,
+	/// Sends finished battles to the server. Off by default, older servers don't take replays.
+	pub upload_replays: bool,
+	/// How cleverly opponents shop, daily challenges always play on Normal
+	pub difficulty: Difficulty,
+	pub controls: ActionMap,
+}
+
+impl Default for Settings {
+	fn default() -> Self {
+		Self {
+			window: WindowSetting::Windowed,
+			resolution: RESOLUTIONS[0],
+			vsync: true,
+			master_volume: 80,
+			music_volume: 60,
+			sfx_volume: 100,
+			wobble: true,
+			upload_replays: false,
+			difficulty: Difficulty::default(),
+			controls: ActionMap::default(),
+		}
+	}
+}
+
+impl Settings {
+	/// The saved settings, or the defaults if there are none (or they can't be read, like on wasm)
+	pub fn load() -> Self {
+		if cfg!(target_arch = "wasm32") {
+			return Self::default();
+		}
+		match std::fs::read_to_string(SETTINGS_PATH) {
+			Ok(json) => serde_json::from_str(&json).unwrap_or_else(|err| {
+				error!("failed to parse {}, using default settings: {}", SETTINGS_PATH, err);
+				Self::default()
+			}),
+			Err(_) => Self::default(),
+		}
+	}
+
+	fn save(&self) {
+		if cfg!(target_arch = "wasm32") {
+			return;
+		}
+		let result = serde_json::to_string_pretty(self)
+			.map_err(|err| err.to_string())
+			.and_then(|json| std::fs::write(SETTINGS_PATH, json).map_err(|err| err.to_string()));
+		if let Err(err) = result {
+			error!("failed to save {}: {}", SETTINGS_PATH, err);
+		}
+	}
+
+	pub fn window_descriptor(&self) -> WindowDescriptor {
+		WindowDescriptor {
+			width: self.resolution.0 as f32,
+			height: self.resolution.1 as f32,
+			present_mode: self.present_mode(),
+			mode: self.window.mode(),
+			..Default::default()
+		}
+	}
+
+	fn present_mode(&self) -> PresentMode {
+		if self.vsync {
+			PresentMode::Fifo
+		} else {
+			PresentMode::Immediate
+		}
+	}
+}
+
+fn next_resolution(resolution: (u32, u32)) -> (u32, u32) {
+	let index = RESOLUTIONS.iter().position(|r| *r == resolution);
+	RESOLUTIONS[index.map_or(0, |i| (i + 1) % RESOLUTIONS.len())]
+}
+
+fn next_volume(volume: u32) -> u32 {
+	if volume >= 100 {
+		0
+	} else {
+		(volume + VOLUME_STEP).min(100)
+	}
+}
+
+fn on_off(on: bool) -> &'static str {
+	if on {
+		"On"
+	} else {
+		"Off"
+	}
+}
+
+// applies changes to the window as they're made, and remembers them for next time
+fn apply_settings(settings: Res<Settings>, mut windows: ResMut<Windows>) {
+	// the window was already created with the loaded settings
+	if !settings.is_changed() || settings.is_added() {
+		return;
+	}
+	if let Some(window) = windows.get_primary_mut() {
+		window.set_mode(settings.window.mode());
+		window.set_resolution(settings.resolution.0 as f32, settings.resolution.1 as f32);
+		window.set_present_mode(settings.present_mode());
+	}
+	settings.save();
+}
+
+struct SettingsData {
+	root: Entity,
+}
+
+/// Cycles through the values of one setting
+#[derive(Component, Clone, Copy)]
+pub enum SettingButton {
+	Difficulty,
+	Window,
+	Resolution,
+	VSync,
+	Wobble,
+	UploadReplays,
+	MasterVolume,
+	MusicVolume,
+	SfxVolume,
+}
+
+impl SettingButton {
+	fn label(&self, settings: &Settings) -> String {
+		match self {
+			SettingButton::Difficulty => format!("Opponents: {}", settings.difficulty.name()),
+			SettingButton::Window => format!("Window: {}", settings.window.name()),
+			SettingButton::Resolution => {
+				format!("Resolution: {}x{}", settings.resolution.0, settings.resolution.1)
+			},
+			SettingButton::VSync => format!("VSync: {}", on_off(settings.vsync)),
+			SettingButton::Wobble => format!("Wobble: {}", on_off(settings.wobble)),
+			SettingButton::UploadReplays => {
+				format!("Share replays: {}", on_off(settings.upload_replays))
+			},
+			SettingButton::MasterVolume => format!("Volume: {}%", settings.master_volume),
+			SettingButton::MusicVolume => format!("Music: {}%", settings.music_volume),
+			SettingButton::SfxVolume => format!("Effects: {}%", settings.sfx_volume),
+		}
+	}
+}
+
+fn spawn_settings(mut commands: Commands, asset_server: Res<AssetServer>, settings: Res<Settings>) {
+	// ui is laid out bottom to top, so columns list their children in reverse
+	let column = || NodeBundle {
+		style: Style {
+			flex_direction: FlexDirection::ColumnReverse,
+			align_items: AlignItems::Center,
+			margin: Rect::all(Val::Px(20.0)),
+			..Default::default()
+		},
+		color: Color::NONE.into(),
+		..Default::default()
+	};
+	let columns = [
+		vec![
+			SettingButton::Difficulty,
+			SettingButton::Window,
+			SettingButton::Resolution,
+			SettingButton::VSync,
+			SettingButton::Wobble,
+			SettingButton::UploadReplays,
+		],
+		vec![SettingButton::MasterVolume, SettingButton::MusicVolume, SettingButton::SfxVolume],
+	];
+
+	let root = commands
+		.spawn_bundle(NodeBundle {
+			style: Style {
+				size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+				position_type: PositionType::Absolute,
+				flex_direction: FlexDirection::ColumnReverse,
+				justify_content: JustifyContent::Center,
+				align_items: AlignItems::Center,
+				..Default::default()
+			},
+			// opened from the pause menu the game is still underneath
+			color: Color::rgba(1.0, 1.0, 1.0, 0.8).into(),
+			..Default::default()
+		})
+		.with_children(|parent| {
+			parent
+				.spawn_bundle(NodeBundle {
+					style: Style { align_items: AlignItems::FlexStart, ..Default::default() },
+					color: Color::NONE.into(),
+					..Default::default()
+				})
+				.with_children(|parent| {
+					for buttons in columns {
+						parent.spawn_bundle(column()).with_children(|parent| {
+							for button in buttons {
+								let label = button.label(&settings);
+								spawn_button(parent, &asset_server, label, 300.0, button);
+							}
+						});
+					}
+				});
+			spawn_button(parent, &asset_server, "Back", 150.0, BackButton);
+		})
+		.id();
+
+	commands.insert_resource(SettingsData { root });
+}
+
+fn settings_buttons(
+	mut settings: ResMut<Settings>,
+	mut pressed: EventReader<ButtonPressed>,
+	buttons: Query<(&SettingButton, &Children)>,
+	mut texts: Query<&mut Text>,
+) {
+	for ButtonPressed(button) in pressed.iter() {
+		if let Ok((setting, children)) = buttons.get(*button) {
+			match setting {
+				SettingButton::Difficulty => settings.difficulty = settings.difficulty.next(),
+				SettingButton::Window => settings.window = settings.window.next(),
+				SettingButton::Resolution => {
+					settings.resolution = next_resolution(settings.resolution)
+				},
+				SettingButton::VSync => settings.vsync = !settings.vsync,
+				SettingButton::Wobble => settings.wobble = !settings.wobble,
+				SettingButton::UploadReplays => settings.upload_replays = !settings.upload_replays,
+				SettingButton::MasterVolume => {
+					settings.master_volume = next_volume(settings.master_volume)
+				},
+				SettingButton::MusicVolume => {
+					settings.music_volume = next_volume(settings.music_volume)
+				},
+				SettingButton::SfxVolume => settings.sfx_volume = next_volume(settings.sfx_volume),
+			}
+			set_button_label(children, &mut texts, setting.label(&settings));
+		}
+	}
+}
+
+fn cleanup_settings(mut commands: Commands, settings: Res<SettingsData>) {
+	commands.entity(settings.root).despawn_recursive();
+}
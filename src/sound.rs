@@ -0,0 +1,275 @@
+use bevy::{asset::LoadState, audio::AudioSink, prelude::*, utils::HashMap};
+
+use crate::{
+	game::{battle::BattleEvent, replay::BattleOutcome, sell::BuddySold, shop::ShopEvent},
+	settings::Settings,
+	widget::ButtonPressed,
+	AppState,
+};
+
+/// Music for each screen and sound effects for gameplay events. Effects are requested with
+/// `PlaySound` events, most of them are sent here in response to other gameplay events.
+pub struct SoundPlugin {
+	pub output: SoundOutput,
+}
+
+/// Where sounds end up. `Silent` doesn't touch bevy's audio at all, so apps without an audio
+/// device (or without bevy's `AudioPlugin`) can still send `PlaySound` events. `Speakers` stays
+/// silent the same way while there is no `assets/audio` directory.
+#[derive(Clone, Copy)]
+pub enum SoundOutput {
+	Speakers,
+	Silent,
+}
+
+// the audio assets don't ship with the game yet, see `audio_assets_present`
+const AUDIO_DIR: &str = "assets/audio";
+
+/// Whether there are any sounds to load. There's no directory to look at on wasm, so there aren't.
+fn audio_assets_present() -> bool {
+	if cfg!(target_arch = "wasm32") {
+		return false;
+	}
+	// the same root bevy's asset server loads from
+	let root = match std::env::var("CARGO_MANIFEST_DIR") {
+		Ok(manifest_dir) => std::path::PathBuf::from(manifest_dir),
+		Err(_) => match std::env::current_exe() {
+			Ok(exe) => exe.parent().map(|dir| dir.to_path_buf()).unwrap_or_default(),
+			Err(_) => return false,
+		},
+	};
+	root.join(AUDIO_DIR).is_dir()
+}
+
+impl Plugin for SoundPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_event::<PlaySound>()
+			.add_system(ui_sounds)
+			.add_system(shop_sounds)
+			.add_system(battle_sounds);
+		match self.output {
+			// rather than failing to load every sound on every launch
+			SoundOutput::Speakers if !audio_assets_present() => {
+				info!("no {} directory, playing without sound", AUDIO_DIR);
+			},
+			SoundOutput::Speakers => {
+				app.add_startup_system(load_sounds)
+					.add_system(
+						play_sounds.after(ui_sounds).after(shop_sounds).after(battle_sounds),
+					)
+					.add_system(play_music);
+			},
+			SoundOutput::Silent => {},
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sound {
+	Click,
+	Buy,
+	Place,
+	Trash,
+	Deny,
+	Attack,
+	Block,
+	Faint,
+	Win,
+	Lose,
+	Tie,
+}
+
+impl Sound {
+	const ALL: [Sound; 11] = [
+		Sound::Click,
+		Sound::Buy,
+		Sound::Place,
+		Sound::Trash,
+		Sound::Deny,
+		Sound::Attack,
+		Sound::Block,
+		Sound::Faint,
+		Sound::Win,
+		Sound::Lose,
+		Sound::Tie,
+	];
+
+	fn path(&self) -> &'static str {
+		match self {
+			Sound::Click => "audio/sfx/click.ogg",
+			Sound::Buy => "audio/sfx/buy.ogg",
+			Sound::Place => "audio/sfx/place.ogg",
+			Sound::Trash => "audio/sfx/trash.ogg",
+			Sound::Deny => "audio/sfx/deny.ogg",
+			Sound::Attack => "audio/sfx/attack.ogg",
+			Sound::Block => "audio/sfx/block.ogg",
+			Sound::Faint => "audio/sfx/faint.ogg",
+			Sound::Win => "audio/sfx/win.ogg",
+			Sound::Lose => "audio/sfx/lose.ogg",
+			Sound::Tie => "audio/sfx/tie.ogg",
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Music {
+	Menu,
+	Shop,
+	Battle,
+}
+
+impl Music {
+	const ALL: [Music; 3] = [Music::Menu, Music::Shop, Music::Battle];
+
+	fn path(&self) -> &'static str {
+		match self {
+			Music::Menu => "audio/music/menu.ogg",
+			Music::Shop => "audio/music/shop.ogg",
+			Music::Battle => "audio/music/battle.ogg",
+		}
+	}
+
+	/// The track for the state stack, overlays like the pause menu keep what's underneath playing
+	pub fn for_states(states: &[AppState]) -> Option<Music> {
+		let below_pause = states.split(|state| *state == AppState::Paused).next()?;
+		match below_pause.last()? {
+			AppState::LoadNetwork | AppState::Connect | AppState::Login => None,
+			AppState::Menu | AppState::Collection | AppState::Leaderboard | AppState::Settings => {
+				Some(Music::Menu)
+			},
+			AppState::Startup | AppState::Shop | AppState::RunSummary => Some(Music::Shop),
+			AppState::Battle | AppState::Replay => Some(Music::Battle),
+			AppState::Paused => None,
+		}
+	}
+}
+
+pub struct PlaySound(pub Sound);
+
+struct SoundHandles {
+	sounds: HashMap<Sound, Handle<AudioSource>>,
+	music: HashMap<Music, Handle<AudioSource>>,
+}
+
+fn ui_sounds(mut pressed: EventReader<ButtonPressed>, mut sounds: EventWriter<PlaySound>) {
+	for _ in pressed.iter() {
+		sounds.send(PlaySound(Sound::Click));
+	}
+}
+
+fn shop_sounds(
+	mut shop_events: EventReader<ShopEvent>,
+	mut sold: EventReader<BuddySold>,
+	mut sounds: EventWriter<PlaySound>,
+) {
+	for event in shop_events.iter() {
+		sounds.send(PlaySound(match event {
+			ShopEvent::Bought => Sound::Buy,
+			ShopEvent::Placed => Sound::Place,
+			ShopEvent::Denied(_) => Sound::Deny,
+		}));
+	}
+	for _ in sold.iter() {
+		sounds.send(PlaySound(Sound::Trash));
+	}
+}
+
+fn battle_sounds(mut battle_events: EventReader<BattleEvent>, mut sounds: EventWriter<PlaySound>) {
+	for event in battle_events.iter() {
+		let sound = match event {
+			BattleEvent::Hit { blocked: true, .. } => Sound::Block,
+			BattleEvent::Hit { .. } => Sound::Attack,
+			BattleEvent::Fainted { .. } => Sound::Faint,
+			BattleEvent::Buffed { .. } => continue,
+			BattleEvent::Finished(BattleOutcome::Win) => Sound::Win,
+			BattleEvent::Finished(BattleOutcome::Lose) => Sound::Lose,
+			BattleEvent::Finished(BattleOutcome::Tie) => Sound::Tie,
+		};
+		sounds.send(PlaySound(sound));
+	}
+}
+
+fn load_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+	commands.insert_resource(SoundHandles {
+		sounds: Sound::ALL
+			.iter()
+			.map(|sound| (*sound, asset_server.load(sound.path())))
+			.collect(),
+		music: Music::ALL
+			.iter()
+			.map(|music| (*music, asset_server.load(music.path())))
+			.collect(),
+	});
+}
+
+// a sound that's missing or still loading is skipped, bevy would otherwise hold up the queue
+// waiting for it
+fn loaded(asset_server: &AssetServer, handle: &Handle<AudioSource>) -> bool {
+	asset_server.get_load_state(handle) == LoadState::Loaded
+}
+
+fn volume(settings: &Settings, category: u32) -> f32 {
+	(settings.master_volume * category) as f32 / (100 * 100) as f32
+}
+
+fn play_sounds(
+	audio: Res<Audio>,
+	asset_server: Res<AssetServer>,
+	handles: Res<SoundHandles>,
+	settings: Res<Settings>,
+	mut requests: EventReader<PlaySound>,
+) {
+	// skipping a battle sends a whole fight's worth of events in one frame
+	let mut played = Vec::new();
+	for PlaySound(sound) in requests.iter() {
+		if played.contains(sound) {
+			continue;
+		}
+		played.push(*sound);
+		let handle = &handles.sounds[sound];
+		if loaded(&asset_server, handle) {
+			audio.play_with_settings(
+				handle.clone(),
+				PlaybackSettings::ONCE.with_volume(volume(&settings, settings.sfx_volume)),
+			);
+		}
+	}
+}
+
+fn play_music(
+	audio: Res<Audio>,
+	asset_server: Res<AssetServer>,
+	handles: Res<SoundHandles>,
+	settings: Res<Settings>,
+	state: Res<State<AppState>>,
+	sinks: Res<Assets<AudioSink>>,
+	mut playing: Local<Option<(Music, Handle<AudioSink>)>>,
+) {
+	let mut states = state.inactives().to_vec();
+	states.push(state.current().clone());
+	let wanted = Music::for_states(&states);
+
+	let current = playing.as_ref().map(|(music, _)| *music);
+	if current != wanted {
+		if let Some((_, sink)) = playing.take() {
+			if let Some(sink) = sinks.get(&sink) {
+				sink.stop();
+			}
+		}
+		if let Some(music) = wanted {
+			let handle = &handles.music[&music];
+			// tried again next frame until it's loaded
+			if loaded(&asset_server, handle) {
+				let sink = audio.play_with_settings(
+					handle.clone(),
+					PlaybackSettings::LOOP.with_volume(volume(&settings, settings.music_volume)),
+				);
+				*playing = Some((music, sink));
+			}
+		}
+	} else if settings.is_changed() {
+		if let Some(sink) = playing.as_ref().and_then(|(_, sink)| sinks.get(sink)) {
+			sink.set_volume(volume(&settings, settings.music_volume));
+		}
+	}
+}
@@ -0,0 +1,119 @@
+use bevy::{prelude::*, render::camera::Camera2d};
+
+use crate::game::shop::world_to_screen;
+
+/// Small ui panels that follow something in the world around the screen, like a buddy's details
+pub struct TooltipPlugin;
+
+impl Plugin for TooltipPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_system(follow_anchors);
+	}
+}
+
+// room kept between a tooltip and the window's edges
+const EDGE_MARGIN: f32 = 8.0;
+
+/// Keeps a ui node centered above `anchor`, `offset` world units away. Despawned along with the
+/// anchor.
+#[derive(Component)]
+pub struct Tooltip {
+	pub anchor: Entity,
+	pub offset: Vec2,
+	text: Entity,
+}
+
+impl Tooltip {
+	pub fn set_text(&self, texts: &mut Query<&mut Text>, value: impl Into<String>) {
+		let value = value.into();
+		if let Ok(mut text) = texts.get_mut(self.text) {
+			// setting it every frame would lay the text out again every frame
+			if text.sections[0].value != value {
+				text.sections[0].value = value;
+			}
+		}
+	}
+}
+
+/// Spawns a tooltip for `anchor`, its text can be changed later with `Tooltip::set_text`
+pub fn spawn_tooltip(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	anchor: Entity,
+	offset: Vec2,
+	text: impl Into<String>,
+) -> Entity {
+	let text = commands
+		.spawn_bundle(TextBundle {
+			text: Text::with_section(
+				text,
+				TextStyle {
+					font: asset_server.load("font/CaveatBrush-Regular.ttf"),
+					font_size: 30.0,
+					color: Color::hex("323232").unwrap(),
+				},
+				Default::default(),
+			),
+			..Default::default()
+		})
+		.id();
+	commands
+		.spawn_bundle(NodeBundle {
+			style: Style {
+				position_type: PositionType::Absolute,
+				padding: Rect::all(Val::Px(12.0)),
+				// off screen until it has been placed
+				position: Rect {
+					left: Val::Px(-10000.0),
+					bottom: Val::Px(0.0),
+					..Default::default()
+				},
+				..Default::default()
+			},
+			color: Color::rgba(1.0, 1.0, 1.0, 0.9).into(),
+			..Default::default()
+		})
+		.insert(Tooltip { anchor, offset, text })
+		.add_child(text)
+		.id()
+}
+
+fn follow_anchors(
+	mut commands: Commands,
+	windows: Res<Windows>,
+	cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+	anchors: Query<&GlobalTransform>,
+	mut tooltips: Query<(Entity, &Tooltip, &Node, &mut Style)>,
+) {
+	let window = match windows.get_primary() {
+		Some(window) => window,
+		None => return,
+	};
+	let (camera, camera_transform) = match cameras.get_single() {
+		Ok(camera) => camera,
+		Err(_) => return,
+	};
+	let window_size = Vec2::new(window.width(), window.height());
+
+	for (entity, tooltip, node, mut style) in tooltips.iter_mut() {
+		let anchor = match anchors.get(tooltip.anchor) {
+			Ok(anchor) => anchor.translation.truncate() + tooltip.offset,
+			Err(_) => {
+				commands.entity(entity).despawn_recursive();
+				continue;
+			},
+		};
+		let screen = world_to_screen(window_size, anchor, camera, camera_transform);
+		// the layout hasn't sized it yet on the first frame
+		if node.size == Vec2::ZERO {
+			continue;
+		}
+		let max = (window_size - node.size - Vec2::splat(EDGE_MARGIN)).max(Vec2::ZERO);
+		let left = (screen.x - node.size.x / 2.0).clamp(EDGE_MARGIN.min(max.x), max.x);
+		let bottom = screen.y.clamp(EDGE_MARGIN.min(max.y), max.y);
+		if style.position.left != Val::Px(left) || style.position.bottom != Val::Px(bottom) {
+			style.position.left = Val::Px(left);
+			style.position.bottom = Val::Px(bottom);
+		}
+	}
+}
@@ -0,0 +1,422 @@
+use crate::game::pause::unpaused;
+use bevy::{ecs::component::TableStorage, prelude::*};
+use std::{f32::consts::PI, time::Duration};
+
+/// Animates components over time. An `Animator<T>` on an entity drives its `T` component with a
+/// `Tween`, or a `Sequence` or `Parallel` of them, and is removed when it's done.
+pub struct TweenPlugin;
+
+impl Plugin for TweenPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_system(animate::<Transform>.with_run_criteria(unpaused))
+			.add_system(animate::<Sprite>.with_run_criteria(unpaused))
+			.add_system(animate::<Text>.with_run_criteria(unpaused));
+	}
+}
+
+/// Easing curves from Robert Penner's set, mapping progress from 0.0 to 1.0 onto a curve from
+/// 0.0 to 1.0. OutBack overshoots on the way.
+#[derive(Copy, Clone)]
+pub enum Ease {
+	Linear,
+	InSine,
+	OutSine,
+	InOutSine,
+	InQuad,
+	OutQuad,
+	InOutCirc,
+	OutBack,
+}
+
+const BACK: f32 = 1.70158;
+
+impl Ease {
+	pub fn ease(&self, x: f32) -> f32 {
+		let x = x.clamp(0.0, 1.0);
+		match self {
+			Ease::Linear => x,
+			Ease::InSine => 1. - (x * PI / 2.).cos(),
+			Ease::OutSine => (x * PI / 2.).sin(),
+			Ease::InOutSine => -((PI * x).cos() - 1.) / 2.,
+			Ease::InQuad => in_pow(x, 2),
+			Ease::OutQuad => out_pow(x, 2),
+			Ease::InOutCirc if x < 0.5 => (1. - (1. - (2. * x).powi(2)).sqrt()) / 2.,
+			Ease::InOutCirc => ((1. - (-2. * x + 2.).powi(2)).sqrt() + 1.) / 2.,
+			Ease::OutBack => 1. + (BACK + 1.) * (x - 1.).powi(3) + BACK * (x - 1.).powi(2),
+		}
+	}
+}
+
+fn in_pow(x: f32, n: i32) -> f32 {
+	x.powi(n)
+}
+
+fn out_pow(x: f32, n: i32) -> f32 {
+	1. - (1. - x).powi(n)
+}
+
+/// Applies a tween's eased progress to the part of a component it animates
+pub trait Lens<T>: Send + Sync + 'static {
+	fn apply(&mut self, target: &mut T, ratio: f32);
+}
+
+pub struct TranslationLens {
+	pub start: Vec3,
+	pub end: Vec3,
+}
+
+impl Lens<Transform> for TranslationLens {
+	fn apply(&mut self, target: &mut Transform, ratio: f32) {
+		target.translation = self.start + (self.end - self.start) * ratio;
+	}
+}
+
+pub struct ScaleLens {
+	pub start: Vec3,
+	pub end: Vec3,
+}
+
+impl Lens<Transform> for ScaleLens {
+	fn apply(&mut self, target: &mut Transform, ratio: f32) {
+		target.scale = self.start + (self.end - self.start) * ratio;
+	}
+}
+
+pub struct RotationLens {
+	pub start: Quat,
+	pub end: Quat,
+}
+
+impl Lens<Transform> for RotationLens {
+	fn apply(&mut self, target: &mut Transform, ratio: f32) {
+		target.rotation = self.start.slerp(self.end, ratio);
+	}
+}
+
+pub struct SpriteColorLens {
+	pub start: Color,
+	pub end: Color,
+}
+
+impl Lens<Sprite> for SpriteColorLens {
+	fn apply(&mut self, target: &mut Sprite, ratio: f32) {
+		target.color = lerp_color(self.start, self.end, ratio);
+	}
+}
+
+/// Colors every section of a text
+pub struct TextColorLens {
+	pub start: Color,
+	pub end: Color,
+}
+
+impl Lens<Text> for TextColorLens {
+	fn apply(&mut self, target: &mut Text, ratio: f32) {
+		let color = lerp_color(self.start, self.end, ratio);
+		for section in target.sections.iter_mut() {
+			section.style.color = color;
+		}
+	}
+}
+
+/// Eases a plain value, for tweens that are ticked by hand rather than by an `Animator`
+pub struct ValueLens {
+	pub start: f32,
+	pub end: f32,
+}
+
+impl Lens<f32> for ValueLens {
+	fn apply(&mut self, target: &mut f32, ratio: f32) {
+		*target = self.start + (self.end - self.start) * ratio;
+	}
+}
+
+fn lerp_color(start: Color, end: Color, ratio: f32) -> Color {
+	let [r, g, b, a] = start.as_rgba_f32();
+	let [r2, g2, b2, a2] = end.as_rgba_f32();
+	let lerp = |from: f32, to: f32| from + (to - from) * ratio;
+	Color::rgba(lerp(r, r2), lerp(g, g2), lerp(b, b2), lerp(a, a2))
+}
+
+/// Something that animates a `T` over time
+pub trait Tweenable<T>: Send + Sync + 'static {
+	/// Moves `delta` further along, returning the time left over once it's finished
+	fn tick(&mut self, delta: Duration, target: &mut T) -> Option<Duration>;
+}
+
+/// Eases one lens from start to end over `duration`
+pub struct Tween<T> {
+	timer: Timer,
+	ease: Ease,
+	lens: Box<dyn Lens<T>>,
+}
+
+impl<T: 'static> Tween<T> {
+	pub fn new(duration: Duration, ease: Ease, lens: impl Lens<T>) -> Self {
+		Self { timer: Timer::new(duration, false), ease, lens: Box::new(lens) }
+	}
+}
+
+impl<T: 'static> Tweenable<T> for Tween<T> {
+	fn tick(&mut self, delta: Duration, target: &mut T) -> Option<Duration> {
+		// there's no progress through nothing, it's done as soon as it starts
+		if self.timer.duration().is_zero() {
+			self.lens.apply(target, self.ease.ease(1.0));
+			return Some(delta);
+		}
+		let elapsed = self.timer.elapsed() + delta;
+		self.timer.tick(delta);
+		self.lens.apply(target, self.ease.ease(self.timer.percent()));
+		if self.timer.finished() {
+			Some(elapsed.saturating_sub(self.timer.duration()))
+		} else {
+			None
+		}
+	}
+}
+
+/// Waits before the next step of a `Sequence`
+pub struct Delay {
+	timer: Timer,
+}
+
+impl Delay {
+	pub fn new(duration: Duration) -> Self {
+		Self { timer: Timer::new(duration, false) }
+	}
+}
+
+impl<T> Tweenable<T> for Delay {
+	fn tick(&mut self, delta: Duration, _target: &mut T) -> Option<Duration> {
+		let elapsed = self.timer.elapsed() + delta;
+		self.timer.tick(delta);
+		if self.timer.finished() {
+			Some(elapsed.saturating_sub(self.timer.duration()))
+		} else {
+			None
+		}
+	}
+}
+
+/// Plays its steps one after the other
+pub struct Sequence<T> {
+	steps: Vec<Box<dyn Tweenable<T>>>,
+	current: usize,
+}
+
+impl<T: 'static> Default for Sequence<T> {
+	fn default() -> Self {
+		Self { steps: Vec::new(), current: 0 }
+	}
+}
+
+impl<T: 'static> Sequence<T> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn then(mut self, step: impl Tweenable<T>) -> Self {
+		self.steps.push(Box::new(step));
+		self
+	}
+}
+
+impl<T: 'static> Tweenable<T> for Sequence<T> {
+	fn tick(&mut self, mut delta: Duration, target: &mut T) -> Option<Duration> {
+		// time left over from a finished step carries into the next one
+		while let Some(step) = self.steps.get_mut(self.current) {
+			delta = step.tick(delta, target)?;
+			self.current += 1;
+		}
+		Some(delta)
+	}
+}
+
+/// Plays its tracks at the same time, finishing with the longest
+pub struct Parallel<T> {
+	tracks: Vec<Box<dyn Tweenable<T>>>,
+}
+
+impl<T: 'static> Default for Parallel<T> {
+	fn default() -> Self {
+		Self { tracks: Vec::new() }
+	}
+}
+
+impl<T: 'static> Parallel<T> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with(mut self, track: impl Tweenable<T>) -> Self {
+		self.tracks.push(Box::new(track));
+		self
+	}
+}
+
+impl<T: 'static> Tweenable<T> for Parallel<T> {
+	fn tick(&mut self, delta: Duration, target: &mut T) -> Option<Duration> {
+		let mut left_over = Some(delta);
+		for track in self.tracks.iter_mut() {
+			left_over = match (left_over, track.tick(delta, target)) {
+				(Some(left_over), Some(track_left_over)) => Some(left_over.min(track_left_over)),
+				_ => None,
+			};
+		}
+		left_over
+	}
+}
+
+/// Plays a tweenable on the entity's `T`, removed once it's done
+pub struct Animator<T> {
+	tweenable: Box<dyn Tweenable<T>>,
+	despawn: bool,
+}
+
+impl<T: Component> Component for Animator<T> {
+	type Storage = TableStorage;
+}
+
+impl<T: 'static> Animator<T> {
+	pub fn new(tweenable: impl Tweenable<T>) -> Self {
+		Self { tweenable: Box::new(tweenable), despawn: false }
+	}
+
+	/// Despawns the entity and its children once done, for effects that only play once
+	pub fn despawn_when_done(mut self) -> Self {
+		self.despawn = true;
+		self
+	}
+}
+
+fn animate<T: Component>(
+	mut commands: Commands,
+	time: Res<Time>,
+	mut animated: Query<(Entity, &mut T, &mut Animator<T>)>,
+) {
+	for (entity, mut target, mut animator) in animated.iter_mut() {
+		if animator.tweenable.tick(time.delta(), &mut *target).is_none() {
+			continue;
+		}
+		if animator.despawn {
+			commands.entity(entity).despawn_recursive();
+		} else {
+			commands.entity(entity).remove::<Animator<T>>();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const ALL: [Ease; 8] = [
+		Ease::Linear,
+		Ease::InSine,
+		Ease::OutSine,
+		Ease::InOutSine,
+		Ease::InQuad,
+		Ease::OutQuad,
+		Ease::InOutCirc,
+		Ease::OutBack,
+	];
+
+	fn assert_close(actual: f32, expected: f32) {
+		assert!((actual - expected).abs() < 1e-4, "expected {}, got {}", expected, actual);
+	}
+
+	#[test]
+	fn eases_start_at_zero_and_end_at_one() {
+		for ease in ALL {
+			assert_close(ease.ease(0.0), 0.0);
+			assert_close(ease.ease(1.0), 1.0);
+		}
+	}
+
+	#[test]
+	fn in_out_eases_are_halfway_at_the_middle() {
+		for ease in [Ease::Linear, Ease::InOutSine, Ease::InOutCirc] {
+			assert_close(ease.ease(0.5), 0.5);
+		}
+	}
+
+	#[test]
+	fn out_eases_mirror_in_eases() {
+		for (ease_in, ease_out) in [(Ease::InSine, Ease::OutSine), (Ease::InQuad, Ease::OutQuad)] {
+			for i in 0..=20 {
+				let x = i as f32 / 20.0;
+				assert_close(ease_out.ease(x), 1.0 - ease_in.ease(1.0 - x));
+			}
+		}
+	}
+
+	#[test]
+	fn eases_match_known_values() {
+		assert_close(Ease::InQuad.ease(0.5), 0.25);
+		assert_close(Ease::OutQuad.ease(0.5), 0.75);
+		assert_close(Ease::OutSine.ease(0.5), (PI / 4.0).sin());
+		assert_close(Ease::InOutCirc.ease(0.25), (1.0 - 0.75f32.sqrt()) / 2.0);
+	}
+
+	#[test]
+	fn out_back_overshoots() {
+		assert!(Ease::OutBack.ease(0.8) > 1.0);
+	}
+
+	#[test]
+	fn eases_clamp_progress() {
+		assert_close(Ease::OutQuad.ease(-1.0), 0.0);
+		assert_close(Ease::OutQuad.ease(2.0), 1.0);
+	}
+
+	fn translation(start: f32, end: f32, seconds: f32) -> Tween<Transform> {
+		Tween::new(
+			Duration::from_secs_f32(seconds),
+			Ease::Linear,
+			TranslationLens { start: Vec3::splat(start), end: Vec3::splat(end) },
+		)
+	}
+
+	#[test]
+	fn sequences_carry_time_over_between_steps() {
+		let mut transform = Transform::default();
+		let mut sequence = Sequence::new()
+			.then(translation(0.0, 1.0, 1.0))
+			.then(Delay::new(Duration::from_secs_f32(1.0)))
+			.then(translation(1.0, 3.0, 1.0));
+		assert!(sequence.tick(Duration::from_secs_f32(0.5), &mut transform).is_none());
+		assert_close(transform.translation.x, 0.5);
+		assert!(sequence.tick(Duration::from_secs_f32(1.0), &mut transform).is_none());
+		assert_close(transform.translation.x, 1.0);
+		assert!(sequence.tick(Duration::from_secs_f32(1.0), &mut transform).is_none());
+		assert_close(transform.translation.x, 2.0);
+		let left_over = sequence.tick(Duration::from_secs_f32(1.0), &mut transform).unwrap();
+		assert_close(left_over.as_secs_f32(), 0.5);
+		assert_close(transform.translation.x, 3.0);
+	}
+
+	#[test]
+	fn zero_length_tweens_finish_right_away() {
+		let mut transform = Transform::default();
+		let mut tween = translation(0.0, 1.0, 0.0);
+		let left_over = tween.tick(Duration::from_secs_f32(0.25), &mut transform).unwrap();
+		assert_close(left_over.as_secs_f32(), 0.25);
+		assert_close(transform.translation.x, 1.0);
+	}
+
+	#[test]
+	fn parallels_finish_with_their_longest_track() {
+		let mut sprite = Sprite::default();
+		let mut parallel = Parallel::new()
+			.with(Tween::new(
+				Duration::from_secs_f32(1.0),
+				Ease::Linear,
+				SpriteColorLens { start: Color::WHITE, end: Color::BLACK },
+			))
+			.with(Delay::new(Duration::from_secs_f32(2.0)));
+		assert!(parallel.tick(Duration::from_secs_f32(1.5), &mut sprite).is_none());
+		assert_eq!(sprite.color, Color::rgba(0.0, 0.0, 0.0, 1.0));
+		let left_over = parallel.tick(Duration::from_secs_f32(1.0), &mut sprite).unwrap();
+		assert_close(left_over.as_secs_f32(), 0.5);
+	}
+}
@@ -2,9 +2,9 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct Color {
-	r: u8,
-	g: u8,
-	b: u8,
+	pub r: u8,
+	pub g: u8,
+	pub b: u8,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -19,11 +19,48 @@ pub struct CharacterDetails {
 pub enum NetworkMessage {
 	Connect(String),
 	Save(UserData),
+	/// A finished battle, encoded as the JSON battle log the game saves to disk
+	UploadReplay(String),
+	DailyScore(DailyScore),
+	/// A finished run, counted towards the best run board
+	RunScore(RunScore),
+	/// Asks for the top entries of a board, answered with `NetworkMessageResponse::Leaderboard`
+	FetchLeaderboard(Leaderboard, u32),
+	/// Asks which NFT each buddy of the collection is, answered with `NetworkMessageResponse::Tokens`
+	FetchTokens,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub enum NetworkMessageResponse {
 	Connect(UserData, Vec<CharacterDetails>),
+	/// The top entries of a board and where this player stands on it, if they're on it at all
+	Leaderboard(Leaderboard, Vec<LeaderboardEntry>, Option<LeaderboardEntry>),
+	/// The token id of each buddy in the `Connect` answer, in the same order
+	Tokens(Vec<Option<String>>),
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub enum Leaderboard {
+	/// Battles won across every run
+	Wins,
+	/// Most trophies won in a single run
+	BestRun,
+	/// Most trophies in the daily challenge for the given date (YYYY-MM-DD)
+	Daily(String),
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct LeaderboardEntry {
+	pub rank: u32,
+	pub name: String,
+	pub score: u32,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct RunScore {
+	pub trophies: u32,
+	pub rounds: u32,
+	pub lives: u32,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -32,6 +69,15 @@ pub struct UserData {
 	pub trophies: (u32, u32),
 }
 
+/// How far a player got in the daily challenge for `date` (YYYY-MM-DD)
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct DailyScore {
+	pub date: String,
+	pub trophies: u32,
+	pub rounds: u32,
+	pub lives: u32,
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct GenericNetworkMessage {
 	pub game_id: GameId,
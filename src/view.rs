@@ -0,0 +1,150 @@
+use bevy::{math::const_vec2, prelude::*, render::camera::Camera2d};
+
+use crate::game::ui::UiRoot;
+
+/// Shows the same part of the world on any window. A virtual resolution is scaled to fit the
+/// window and the rest is letterboxed, portrait windows get a portrait virtual resolution. The
+/// game's ui is fitted to the same area, so it can be laid out against its edges.
+pub struct ViewPlugin;
+
+impl Plugin for ViewPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<View>()
+			.add_startup_system(spawn_letterbox)
+			.add_system(fit_view)
+			.add_system(position_letterbox.after(fit_view))
+			.add_system(fit_ui_root.after(fit_view));
+	}
+}
+
+const LANDSCAPE: Vec2 = const_vec2!([1280.0, 720.0]);
+const PORTRAIT: Vec2 = const_vec2!([720.0, 1280.0]);
+const LETTERBOX_COLOR: Color = Color::rgb(0.15, 0.15, 0.2);
+// above everything in the world, the camera sits at 999.9
+const Z_LETTERBOX: f32 = 900.0;
+// bars reach this far past the view, enough for any window shape
+const LETTERBOX_SIZE: f32 = 100_000.0;
+
+/// The part of the world that's on screen
+pub struct View {
+	/// How far past the virtual resolution to zoom out, so bigger teams fit
+	pub zoom: f32,
+	/// The virtual resolution for the window's orientation
+	pub virtual_size: Vec2,
+	/// The visible world area in world units, centered on the origin
+	pub size: Vec2,
+	/// Where that area is in the window, in logical pixels from the bottom left
+	pub viewport_position: Vec2,
+	pub viewport_size: Vec2,
+}
+
+impl Default for View {
+	fn default() -> Self {
+		Self {
+			zoom: 1.0,
+			virtual_size: LANDSCAPE,
+			size: LANDSCAPE,
+			viewport_position: Vec2::ZERO,
+			viewport_size: LANDSCAPE,
+		}
+	}
+}
+
+impl View {
+	pub fn half_size(&self) -> Vec2 {
+		self.size / 2.0
+	}
+
+	pub fn is_portrait(&self) -> bool {
+		self.size.y > self.size.x
+	}
+
+	/// Whether a window position (logical pixels from the bottom left) is in the view rather than
+	/// on the letterbox
+	pub fn contains(&self, screen_pos: Vec2) -> bool {
+		let relative = screen_pos - self.viewport_position;
+		relative.cmpge(Vec2::ZERO).all() && relative.cmple(self.viewport_size).all()
+	}
+}
+
+#[derive(Component, Clone, Copy)]
+enum LetterboxBar {
+	Left,
+	Right,
+	Top,
+	Bottom,
+}
+
+fn fit_view(
+	windows: Res<Windows>,
+	mut view: ResMut<View>,
+	mut cameras: Query<&mut OrthographicProjection, With<Camera2d>>,
+) {
+	let window = match windows.get_primary() {
+		Some(window) => window,
+		None => return,
+	};
+	let window_size = Vec2::new(window.width(), window.height());
+	// minimized
+	if window_size.min_element() <= 0.0 {
+		return;
+	}
+
+	let virtual_size = if window_size.y > window_size.x { PORTRAIT } else { LANDSCAPE };
+	let size = virtual_size * view.zoom;
+	let scale = (size / window_size).max_element();
+	let viewport_size = size / scale;
+	view.virtual_size = virtual_size;
+	view.size = size;
+	view.viewport_size = viewport_size;
+	view.viewport_position = (window_size - viewport_size) / 2.0;
+
+	for mut projection in cameras.iter_mut() {
+		projection.scale = scale;
+	}
+}
+
+fn spawn_letterbox(mut commands: Commands) {
+	for bar in [LetterboxBar::Left, LetterboxBar::Right, LetterboxBar::Top, LetterboxBar::Bottom] {
+		commands
+			.spawn_bundle(SpriteBundle {
+				sprite: Sprite {
+					color: LETTERBOX_COLOR,
+					custom_size: Some(Vec2::splat(LETTERBOX_SIZE)),
+					..Default::default()
+				},
+				..Default::default()
+			})
+			.insert(bar);
+	}
+}
+
+// each bar is a huge square with one edge on the view's edge
+fn position_letterbox(view: Res<View>, mut bars: Query<(&LetterboxBar, &mut Transform)>) {
+	let edge = view.half_size() + Vec2::splat(LETTERBOX_SIZE / 2.0);
+	for (bar, mut transform) in bars.iter_mut() {
+		let position = match bar {
+			LetterboxBar::Left => Vec2::new(-edge.x, 0.0),
+			LetterboxBar::Right => Vec2::new(edge.x, 0.0),
+			LetterboxBar::Top => Vec2::new(0.0, edge.y),
+			LetterboxBar::Bottom => Vec2::new(0.0, -edge.y),
+		};
+		*transform = Transform::from_translation(position.extend(Z_LETTERBOX));
+	}
+}
+
+fn fit_ui_root(view: Res<View>, mut roots: Query<&mut Style, With<UiRoot>>) {
+	let position = Rect {
+		left: Val::Px(view.viewport_position.x),
+		bottom: Val::Px(view.viewport_position.y),
+		..Default::default()
+	};
+	let size = Size::new(Val::Px(view.viewport_size.x), Val::Px(view.viewport_size.y));
+	for mut style in roots.iter_mut() {
+		// only touched when it differs, changing a style relayouts the ui
+		if style.position != position || style.size != size {
+			style.position = position;
+			style.size = size;
+		}
+	}
+}
@@ -0,0 +1,227 @@
+use bevy::{ecs::system::EntityCommands, prelude::*, ui::FocusPolicy};
+
+use crate::actions::{ActionPressed, GameAction};
+
+pub const NORMAL_BUTTON: Color = Color::rgb(0.4, 0.4, 0.8);
+pub const HOVERED_BUTTON: Color = Color::rgb(0.6, 0.6, 0.9);
+
+/// Shared buttons. Menu buttons can be clicked, or focused and pressed with the directional and
+/// select actions (arrow keys and enter, or a gamepad's d-pad and south face button by default).
+/// Buttons over the game only take clicks. Either way a `ButtonPressed` event is sent.
+pub struct WidgetPlugin;
+
+impl Plugin for WidgetPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_event::<ButtonPressed>()
+			.init_resource::<Focus>()
+			.add_system(mouse_buttons)
+			.add_system(focus_navigation.after(mouse_buttons))
+			.add_system(button_colors.after(focus_navigation));
+	}
+}
+
+/// Any button spawned by the functions here, clicking it sends `ButtonPressed`
+#[derive(Component)]
+pub struct WidgetButton;
+
+/// A button spawned by `spawn_button` that can take keyboard and gamepad focus
+#[derive(Component)]
+pub struct Focusable;
+
+/// Keeps a button highlighted, like the option currently in use
+#[derive(Component)]
+pub struct Selected;
+
+// image buttons show their image as is
+#[derive(Component)]
+struct ImageButton;
+
+/// The button that enter / the south face button presses
+#[derive(Default)]
+pub struct Focus(pub Option<Entity>);
+
+pub struct ButtonPressed(pub Entity);
+
+/// Spawns a labelled button with the usual styling, `marker` tells the buttons apart
+pub fn spawn_button(
+	parent: &mut ChildBuilder,
+	asset_server: &AssetServer,
+	label: impl Into<String>,
+	width: f32,
+	marker: impl Component,
+) -> Entity {
+	let style = Style {
+		size: Size::new(Val::Px(width), Val::Px(65.0)),
+		margin: Rect::all(Val::Px(10.0)),
+		..Default::default()
+	};
+	labelled_button(parent, asset_server, label, style)
+		.insert(Focusable)
+		.insert(marker)
+		.id()
+}
+
+/// A labelled button over the game, sized and placed by `style`. It takes clicks but not
+/// keyboard focus, the shop cursor has the arrow keys there.
+pub fn spawn_game_button(
+	parent: &mut ChildBuilder,
+	asset_server: &AssetServer,
+	label: impl Into<String>,
+	style: Style,
+	marker: impl Component,
+) -> Entity {
+	labelled_button(parent, asset_server, label, style).insert(marker).id()
+}
+
+fn labelled_button<'w, 's, 'a>(
+	parent: &'a mut ChildBuilder<'w, 's, '_>,
+	asset_server: &AssetServer,
+	label: impl Into<String>,
+	style: Style,
+) -> EntityCommands<'w, 's, 'a> {
+	let mut button = parent.spawn_bundle(ButtonBundle {
+		style: Style {
+			// horizontally center child text
+			justify_content: JustifyContent::Center,
+			// vertically center child text
+			align_items: AlignItems::Center,
+			..style
+		},
+		color: NORMAL_BUTTON.into(),
+		..Default::default()
+	});
+	button.insert(WidgetButton).with_children(|parent| {
+		parent.spawn_bundle(TextBundle {
+			text: Text::with_section(
+				label,
+				TextStyle {
+					font: asset_server.load("font/AmaticSC-Bold.ttf"),
+					font_size: 40.0,
+					color: Color::rgb(0.9, 0.9, 0.9),
+				},
+				Default::default(),
+			),
+			..Default::default()
+		});
+	});
+	button
+}
+
+/// A button over the game showing `image` instead of a label, placed like `spawn_game_button`
+pub fn spawn_image_button(
+	parent: &mut ChildBuilder,
+	asset_server: &AssetServer,
+	image: &str,
+	style: Style,
+	marker: impl Component,
+) -> Entity {
+	parent
+		.spawn_bundle(ButtonBundle {
+			style: Style {
+				justify_content: JustifyContent::Center,
+				align_items: AlignItems::Center,
+				..style
+			},
+			color: Color::NONE.into(),
+			..Default::default()
+		})
+		.insert(WidgetButton)
+		.insert(ImageButton)
+		.insert(marker)
+		.with_children(|parent| {
+			parent
+				.spawn_bundle(ImageBundle {
+					image: asset_server.load(image).into(),
+					..Default::default()
+				})
+				.insert(FocusPolicy::Pass);
+		})
+		.id()
+}
+
+/// Changes the label of a button made by `spawn_button`
+pub fn set_button_label(button: &Children, texts: &mut Query<&mut Text>, label: impl Into<String>) {
+	let label = label.into();
+	for child in button.iter() {
+		if let Ok(mut text) = texts.get_mut(*child) {
+			text.sections[0].value = label.clone();
+		}
+	}
+}
+
+fn mouse_buttons(
+	mut focus: ResMut<Focus>,
+	mut pressed: EventWriter<ButtonPressed>,
+	interactions: Query<
+		(Entity, &Interaction, Option<&Focusable>),
+		(Changed<Interaction>, With<WidgetButton>),
+	>,
+) {
+	for (entity, interaction, focusable) in interactions.iter() {
+		match interaction {
+			Interaction::Clicked => pressed.send(ButtonPressed(entity)),
+			// hovering moves the focus too, so there's only ever one highlighted button
+			Interaction::Hovered => focus.0 = Some(entity),
+			// buttons over the game can't be navigated back to, so they let go of it
+			Interaction::None if focusable.is_none() && focus.0 == Some(entity) => focus.0 = None,
+			Interaction::None => {},
+		}
+	}
+}
+
+fn focus_navigation(
+	keys: Res<Input<KeyCode>>,
+	mut actions: EventReader<ActionPressed>,
+	mut focus: ResMut<Focus>,
+	mut pressed: EventWriter<ButtonPressed>,
+	buttons: Query<(Entity, &GlobalTransform, &Style), With<Focusable>>,
+) {
+	let (mut previous, mut next, mut press) = (false, keys.just_pressed(KeyCode::Tab), false);
+	for ActionPressed(action) in actions.iter() {
+		match action {
+			GameAction::Up | GameAction::Left => previous = true,
+			GameAction::Down | GameAction::Right => next = true,
+			GameAction::Select => press = true,
+			_ => {},
+		}
+	}
+
+	// reading order: top to bottom, then left to right (ui y points up)
+	let mut order = buttons
+		.iter()
+		.filter(|(_, _, style)| style.display != Display::None)
+		.map(|(entity, transform, _)| (entity, transform.translation))
+		.collect::<Vec<_>>();
+	order.sort_by(|(_, a), (_, b)| b.y.total_cmp(&a.y).then(a.x.total_cmp(&b.x)));
+	let current = focus
+		.0
+		.and_then(|focused| order.iter().position(|(entity, _)| *entity == focused));
+
+	if previous || next {
+		let index = match current {
+			None => 0,
+			Some(i) if next => (i + 1) % order.len(),
+			Some(i) => (i + order.len() - 1) % order.len(),
+		};
+		focus.0 = order.get(index).map(|(entity, _)| *entity);
+	} else if press {
+		match current {
+			Some(i) => pressed.send(ButtonPressed(order[i].0)),
+			None => focus.0 = order.first().map(|(entity, _)| *entity),
+		}
+	}
+}
+
+fn button_colors(
+	focus: Res<Focus>,
+	mut buttons: Query<
+		(Entity, &Interaction, &mut UiColor, Option<&Selected>),
+		(With<WidgetButton>, Without<ImageButton>),
+	>,
+) {
+	for (entity, interaction, mut color, selected) in buttons.iter_mut() {
+		let highlighted =
+			focus.0 == Some(entity) || *interaction == Interaction::Clicked || selected.is_some();
+		*color = if highlighted { HOVERED_BUTTON.into() } else { NORMAL_BUTTON.into() };
+	}
+}